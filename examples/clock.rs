@@ -1,9 +1,10 @@
-use std::f64::consts::FRAC_PI_6;
+use raytracer::utils::consts::FRAC_PI_6;
 
 use raytracer::canvas::*;
 use raytracer::color::*;
 use raytracer::matrix::*;
 use raytracer::point::*;
+use raytracer::utils::Float;
 
 fn main() {
     plot_clock(1000, "./images/ppm/clock.ppm");
@@ -16,13 +17,14 @@ fn plot_clock(dim: usize, path: &str) {
     let angle_inc = FRAC_PI_6;
 
     // radius of clock is 3/8 of a side dimension
-    let r = 3.0 / 8.0 * dim as f64;
+    let r = 3.0 / 8.0 * dim as Float;
 
     // translation will be to the center
-    let tc = Matrix::translation(dim as f64 / 2.0, dim as f64 / 2.0, 0.0);
+    let tc = Matrix::translation(dim as Float / 2.0, dim as Float / 2.0, 0.0);
 
-    // translation to 12:00
-    let tt = Matrix::translation(r, 0.0, 0.0);
+    // translations to the inner and outer ends of an hour mark at 12:00
+    let tt_inner = Matrix::translation(0.9 * r, 0.0, 0.0);
+    let tt_outer = Matrix::translation(r, 0.0, 0.0);
 
     for i in 0..12 {
         // initialize the point to the origin
@@ -31,14 +33,21 @@ fn plot_clock(dim: usize, path: &str) {
         let p = Point::ORIGIN;
 
         // rotation will be some multiple of angle_inc
-        let tr = Matrix::rotation_z(angle_inc * i as f64);
+        let tr = Matrix::rotation_z(angle_inc * i as Float);
 
         // transformation order is:
         // 1.  move the point to the 12:00 position
         // 2.  rotate to the correct hour position (rotation is always around the origin)
         // 3.  translate the origin to the center of the canvas
-        let h = tc * tr * tt * p;
-        canvas.write_pixel(h.x as usize, h.y as usize, pixel_color);
+        let inner = tc * tr * tt_inner * p;
+        let outer = tc * tr * tt_outer * p;
+        canvas.draw_line(
+            inner.x.round() as isize,
+            inner.y.round() as isize,
+            outer.x.round() as isize,
+            outer.y.round() as isize,
+            pixel_color,
+        );
     }
 
     canvas.write_ppm(path);
@@ -1,4 +1,4 @@
-use std::f64::consts::FRAC_PI_2;
+use raytracer::utils::consts::FRAC_PI_2;
 
 use raytracer::camera::*;
 use raytracer::color::*;
@@ -28,7 +28,7 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
     );
 
     let light = PointLight::new(Point::new(2.0, 10.0, -5.0), Color::new(0.9, 0.9, 0.9));
-    world.light = light;
+    world.add_light(light);
 
     // wall
     let wall_material = Material {
@@ -60,6 +60,7 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
         reflective: 0.9,
         transparency: 0.9,
         refractive_index: 1.5,
+        ..Default::default()
     };
 
     let glass_sphere = Shape::from(Sphere::new()).with_material(glass_sphere_material);
@@ -76,6 +77,7 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
         reflective: 0.9,
         transparency: 0.9,
         refractive_index: 1.0000034,
+        ..Default::default()
     };
 
     let hollow_center = Shape::from(Sphere::new())
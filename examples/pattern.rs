@@ -7,9 +7,9 @@ use raytracer::point::*;
 use raytracer::point_light::*;
 use raytracer::shapes::Plane;
 use raytracer::shapes::{Shape, Sphere};
+use raytracer::utils::consts::*;
 use raytracer::vector::*;
 use raytracer::world::*;
-use std::f64::consts::*;
 
 fn main() {
     render_scene(4000, 2000, "./images/ppm/pattern.ppm");
@@ -21,10 +21,10 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
 
     // light
     let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
-    world.light = light;
+    world.add_light(light);
 
     // camera
-    let mut camera = Camera::new(hsize, vsize, std::f64::consts::PI / 3.0);
+    let mut camera = Camera::new(hsize, vsize, raytracer::utils::consts::PI / 3.0);
     camera.transform = Matrix::view_transform(
         Point::new(0.0, 1.5, -5.0),
         Point::new(0.0, 1.0, 0.0),
@@ -0,0 +1,155 @@
+use raytracer::utils::consts::PI;
+
+use raytracer::camera::*;
+use raytracer::color::*;
+use raytracer::material::Material;
+use raytracer::matrix::*;
+use raytracer::pattern::*;
+use raytracer::point::*;
+use raytracer::point_light::*;
+use raytracer::shapes::{Cone, Cylinder, Mesh, Shape, Triangle};
+use raytracer::vector::*;
+use raytracer::world::*;
+
+fn main() {
+    let (world, camera) = scene(1600, 800);
+    camera
+        .render(&world)
+        .write_ppm("./images/ppm/primitives.ppm");
+}
+
+// one of each bounded primitive, plus a box, standing on a reflective checker floor
+fn scene(hsize: usize, vsize: usize) -> (World, Camera) {
+    let mut world = World::empty();
+    world.add_light(PointLight::new(Point::new(-6.0, 8.0, -8.0), Color::WHITE));
+
+    let floor = Shape::plane().with_material(Material {
+        pattern: Some(Pattern::checker(
+            Color::new(0.15, 0.15, 0.15),
+            Color::new(0.85, 0.85, 0.85),
+        )),
+        specular: 0.0,
+        reflective: 0.3,
+        ..Default::default()
+    });
+    world.objects.push(floor);
+
+    let sphere = Shape::sphere()
+        .with_transform(Transformation::translation(-3.0, 1.0, 0.0))
+        .with_material(
+            Material::new()
+                .with_color(Color::CRIMSON)
+                .with_reflective(0.1),
+        );
+    world.objects.push(sphere);
+
+    let cylinder = Shape::from(Cylinder::new().with_bounds(0.0, 2.0).with_closed(true))
+        .with_transform(
+            Transformation::translation(0.0, 0.0, 0.5) * Transformation::scaling(0.8, 1.0, 0.8),
+        )
+        .with_material(
+            Material::new()
+                .with_color(Color::STEEL_BLUE)
+                .with_reflective(0.1),
+        );
+    world.objects.push(cylinder);
+
+    // a cone standing on its base: the lower nappe, lifted so the base
+    // (at y = -1) rests on the floor
+    let cone = Shape::from(Cone::new().with_bounds(-1.0, 0.0).with_closed(true))
+        .with_transform(
+            Transformation::translation(3.0, 1.5, 0.0) * Transformation::scaling(1.0, 1.5, 1.0),
+        )
+        .with_material(
+            Material::new()
+                .with_color(Color::GOLDENROD)
+                .with_reflective(0.1),
+        );
+    world.objects.push(cone);
+
+    let cube = Shape::from(cube())
+        .with_transform(
+            Transformation::translation(-1.2, 0.6, 2.5)
+                * Transformation::rotation_y(PI / 6.0)
+                * Transformation::scaling(0.6, 0.6, 0.6),
+        )
+        .with_material(
+            Material::new()
+                .with_color(Color::FOREST_GREEN)
+                .with_reflective(0.1),
+        );
+    world.objects.push(cube);
+
+    let mut camera = Camera::new(hsize, vsize, PI / 3.0);
+    camera.transform = Matrix::view_transform(
+        Point::new(0.0, 3.5, -9.0),
+        Point::new(0.0, 0.8, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+
+    (world, camera)
+}
+
+// a closed cube spanning -1..1 on every axis; there's no cube primitive, so
+// it's a mesh of two triangles per face
+fn cube() -> Mesh {
+    // corner i has bit 0 set for +x, bit 1 for +y and bit 2 for +z
+    let corner = |i: usize| {
+        let axis = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+        Point::new(axis(1), axis(2), axis(4))
+    };
+
+    // the four corners of each face, in order around its edge
+    let faces = [
+        [0, 1, 3, 2],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 3, 7, 6],
+        [0, 2, 6, 4],
+        [1, 3, 7, 5],
+    ];
+    let triangles = faces
+        .iter()
+        .flat_map(|&[a, b, c, d]| {
+            [
+                Triangle::new(corner(a), corner(b), corner(c)),
+                Triangle::new(corner(a), corner(c), corner(d)),
+            ]
+        })
+        .collect();
+
+    Mesh::new(triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_scene_renders() {
+        let (world, camera) = scene(10, 10);
+        let image = camera.render(&world);
+
+        assert_eq!(image.pixels.len(), 100);
+        assert!(image.pixels.iter().all(|c| c.red.is_finite()));
+    }
+
+    #[test]
+    fn every_primitive_is_in_view() {
+        let (world, camera) = scene(40, 20);
+        let ids = camera.render_object_ids(&world);
+
+        for i in 0..world.objects.len() {
+            assert!(ids.contains(&Some(i)), "object {i} is not visible");
+        }
+    }
+
+    #[test]
+    fn cube_mesh_is_closed() {
+        let cube = cube();
+
+        assert_eq!(cube.triangles.len(), 12);
+        assert!(cube.local_contains_point(Point::new(0.3, -0.2, 0.5)));
+        assert!(!cube.local_contains_point(Point::new(1.5, 0.0, 0.0)));
+    }
+}
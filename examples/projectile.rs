@@ -41,21 +41,26 @@ fn plot_trajectory(mut p: Projectile, e: &Environment, path: &str) {
     let mut canvas = Canvas::empty(1800, 1100);
     let pixel_color = Color::new(0.85, 0.35, 0.40);
 
+    canvas.clear(Color::new(0.05, 0.05, 0.1));
+
     while p.position.y > 0.0 {
-        canvas.write_pixel(
-            p.position.x.round() as usize,
-            (1100_f64 - p.position.y).round() as usize,
+        let from = p.position;
+        tick(&mut p, e);
+
+        // connect successive positions so the trajectory is continuous
+        canvas.draw_line(
+            from.x.round() as isize,
+            (1100.0 - from.y).round() as isize,
+            p.position.x.round() as isize,
+            (1100.0 - p.position.y).round() as isize,
             pixel_color,
         );
-        p = tick(p, e);
     }
 
     canvas.write_ppm(path);
 }
 
-fn tick(proj: Projectile, env: &Environment) -> Projectile {
-    Projectile {
-        position: proj.position + proj.velocity,
-        velocity: proj.velocity + env.gravity + env.wind,
-    }
+fn tick(proj: &mut Projectile, env: &Environment) {
+    proj.position += proj.velocity;
+    proj.velocity += env.gravity + env.wind;
 }
@@ -1,4 +1,4 @@
-use std::f64::consts::FRAC_PI_2;
+use raytracer::utils::consts::FRAC_PI_2;
 
 use raytracer::camera::*;
 use raytracer::color::*;
@@ -30,7 +30,7 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
 
     // light
     let light = PointLight::new(Point::new(-4.9, 4.9, -1.0), Color::WHITE);
-    world.light = light;
+    world.add_light(light);
 
     // wall material
     let wall_material = Material {
@@ -18,10 +18,10 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
 
     // light
     let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
-    world.light = light;
+    world.add_light(light);
 
     // camera
-    let mut camera = Camera::new(hsize, vsize, std::f64::consts::PI / 3.0);
+    let mut camera = Camera::new(hsize, vsize, raytracer::utils::consts::PI / 3.0);
     camera.transform = Matrix::view_transform(
         Point::new(0.0, 1.5, -5.0),
         Point::new(0.0, 1.0, 0.0),
@@ -43,8 +43,8 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
 
     // left wall
     let left_wall_transform = Transformation::translation(0.0, 0.0, 5.0)
-        * Transformation::rotation_y(std::f64::consts::PI / 4.0)
-        * Transformation::rotation_z(std::f64::consts::PI / 2.0)
+        * Transformation::rotation_y(raytracer::utils::consts::PI / 4.0)
+        * Transformation::rotation_z(raytracer::utils::consts::PI / 2.0)
         * Transformation::scaling(10.0, 0.01, 10.0);
 
     let left_wall = Shape::from(Sphere::new())
@@ -54,8 +54,8 @@ fn render_scene(hsize: usize, vsize: usize, path: &str) {
 
     // right wall
     let right_wall_transform = Transformation::translation(0.0, 0.0, 5.0)
-        * Transformation::rotation_y(std::f64::consts::PI / 4.0)
-        * Transformation::rotation_x(std::f64::consts::PI / 2.0)
+        * Transformation::rotation_y(raytracer::utils::consts::PI / 4.0)
+        * Transformation::rotation_x(raytracer::utils::consts::PI / 2.0)
         * Transformation::scaling(10.0, 0.01, 10.0);
 
     let right_wall = Shape::from(Sphere::new())
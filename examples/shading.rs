@@ -5,6 +5,7 @@ use raytracer::point::*;
 use raytracer::point_light::*;
 use raytracer::ray::*;
 use raytracer::shapes::{Shape, Sphere};
+use raytracer::utils::Float;
 
 fn main() {
     render_shaded_sphere(2000, "./images/ppm/shading.ppm");
@@ -14,7 +15,7 @@ fn render_shaded_sphere(dim: usize, path: &str) {
     let canvas_pixels = dim;
     let wall_size = 7.0;
     let wall_z = 10.0;
-    let pixel_size = wall_size / canvas_pixels as f64;
+    let pixel_size = wall_size / canvas_pixels as Float;
     let half = wall_size / 2.0;
     let ray_origin = Point::new(0.0, 0.0, -5.0);
 
@@ -33,10 +34,10 @@ fn render_shaded_sphere(dim: usize, path: &str) {
     let light = PointLight::new(light_position, light_color);
 
     for y in 0..canvas_pixels {
-        let world_y = half - pixel_size * y as f64;
+        let world_y = half - pixel_size * y as Float;
 
         for x in 0..canvas_pixels {
-            let world_x = -half + pixel_size * x as f64;
+            let world_x = -half + pixel_size * x as Float;
 
             let position = Point::new(world_x, world_y, wall_z);
             let r = Ray::new(ray_origin, (position - ray_origin).normalize());
@@ -0,0 +1,137 @@
+use crate::color::Color;
+use crate::light::{Light, LightSource};
+use crate::point::Point;
+use crate::utils::Float;
+use crate::vector::Vector;
+use crate::world::World;
+
+// A rectangular light made of usteps x vsteps cells. Shadows are softened by
+// casting one shadow ray toward the center of each cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaLight {
+    pub corner: Point,
+    pub uvec: Vector, // edge of a single cell along u
+    pub usteps: usize,
+    pub vvec: Vector, // edge of a single cell along v
+    pub vsteps: usize,
+    pub intensity: Color,
+    pub position: Point, // center of the light
+}
+
+impl AreaLight {
+    pub fn new(
+        corner: Point,
+        full_uvec: Vector,
+        usteps: usize,
+        full_vvec: Vector,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        let usteps = usteps.max(1);
+        let vsteps = vsteps.max(1);
+
+        Self {
+            corner,
+            uvec: full_uvec / usteps as Float,
+            usteps,
+            vvec: full_vvec / vsteps as Float,
+            vsteps,
+            intensity,
+            position: corner + full_uvec / 2.0 + full_vvec / 2.0,
+        }
+    }
+
+    // the center of cell (u, v) on the light
+    pub fn point_on_light(&self, u: usize, v: usize) -> Point {
+        self.corner + self.uvec * (u as Float + 0.5) + self.vvec * (v as Float + 0.5)
+    }
+
+    // number of shadow samples taken once `multiplier` is applied; at least 1 and
+    // at most one per cell, so multipliers above 1.0 take every cell once
+    pub fn sample_count(&self, multiplier: Float) -> usize {
+        let cells = self.usteps * self.vsteps;
+        ((cells as Float * multiplier).round() as usize).clamp(1, cells)
+    }
+
+    // sample points spread evenly across the light's cells
+    pub fn samples(&self, multiplier: Float) -> impl Iterator<Item = Point> + '_ {
+        let cells = self.usteps * self.vsteps;
+        let n = self.sample_count(multiplier);
+
+        (0..n).map(move |k| {
+            let cell = k * cells / n;
+            self.point_on_light(cell % self.usteps, cell / self.usteps)
+        })
+    }
+}
+
+// shaded as if all of its light came from the center, shadowed per sample
+impl LightSource for AreaLight {
+    fn intensity_at(&self, point: Point, world: &World) -> Float {
+        world.intensity_at(&Light::Area(*self), point)
+    }
+
+    fn direction_to(&self, point: Point) -> Vector {
+        (self.position - point).normalize()
+    }
+
+    fn color(&self) -> Color {
+        self.intensity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn creating_an_area_light() {
+        let corner = Point::ORIGIN;
+        let v1 = Vector::new(2.0, 0.0, 0.0);
+        let v2 = Vector::new(0.0, 0.0, 1.0);
+        let light = AreaLight::new(corner, v1, 4, v2, 2, Color::WHITE);
+
+        assert_eq!(light.corner, corner);
+        assert_abs_diff_eq!(light.uvec, Vector::new(0.5, 0.0, 0.0));
+        assert_eq!(light.usteps, 4);
+        assert_abs_diff_eq!(light.vvec, Vector::new(0.0, 0.0, 0.5));
+        assert_eq!(light.vsteps, 2);
+        assert_abs_diff_eq!(light.position, Point::new(1.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn finding_a_single_point_on_an_area_light() {
+        let light = AreaLight::new(
+            Point::ORIGIN,
+            Vector::new(2.0, 0.0, 0.0),
+            4,
+            Vector::new(0.0, 0.0, 1.0),
+            2,
+            Color::WHITE,
+        );
+
+        assert_abs_diff_eq!(light.point_on_light(0, 0), Point::new(0.25, 0.0, 0.25));
+        assert_abs_diff_eq!(light.point_on_light(1, 0), Point::new(0.75, 0.0, 0.25));
+        assert_abs_diff_eq!(light.point_on_light(0, 1), Point::new(0.25, 0.0, 0.75));
+        assert_abs_diff_eq!(light.point_on_light(2, 0), Point::new(1.25, 0.0, 0.25));
+        assert_abs_diff_eq!(light.point_on_light(3, 1), Point::new(1.75, 0.0, 0.75));
+    }
+
+    #[test]
+    fn multiplier_scales_sample_count() {
+        let light = AreaLight::new(
+            Point::ORIGIN,
+            Vector::new(2.0, 0.0, 0.0),
+            4,
+            Vector::new(0.0, 0.0, 1.0),
+            4,
+            Color::WHITE,
+        );
+
+        assert_eq!(light.samples(1.0).count(), 16);
+        assert_eq!(light.samples(0.5).count(), 8);
+        assert_eq!(light.samples(0.0).count(), 1);
+        assert_eq!(light.samples(4.0).count(), 16);
+    }
+}
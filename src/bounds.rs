@@ -0,0 +1,108 @@
+use crate::matrix::Matrix;
+use crate::point::Point;
+
+// An axis-aligned box, given by its lowest and highest corners
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    // the smallest box holding every point; None if there are no points
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, p| {
+            Some(match bounds {
+                None => Bounds::new(p, p),
+                Some(Bounds { min, max }) => Bounds::new(
+                    Point::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+                ),
+            })
+        })
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        let (lo, hi) = (self.min, self.max);
+        [
+            Point::new(lo.x, lo.y, lo.z),
+            Point::new(lo.x, lo.y, hi.z),
+            Point::new(lo.x, hi.y, lo.z),
+            Point::new(lo.x, hi.y, hi.z),
+            Point::new(hi.x, lo.y, lo.z),
+            Point::new(hi.x, lo.y, hi.z),
+            Point::new(hi.x, hi.y, lo.z),
+            Point::new(hi.x, hi.y, hi.z),
+        ]
+    }
+
+    // the tightest axis-aligned box around this box once transformed by `m`;
+    // every corner is transformed, since a rotation can carry any of them
+    // (not just min and max) to the new extremes
+    pub fn transform(&self, m: Matrix<4>) -> Bounds {
+        let corners = self.corners().map(|c| m * c);
+        Bounds::from_points(corners).expect("a box always has corners")
+    }
+
+    pub fn center(&self) -> Point {
+        Point::midpoint(self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Float;
+    use crate::utils::consts::FRAC_PI_4;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn bounds_of_points_enclose_them_all() {
+        let b = Bounds::from_points([
+            Point::new(-1.0, 2.0, 0.5),
+            Point::new(3.0, -4.0, 0.0),
+            Point::new(0.0, 0.0, 7.0),
+        ])
+        .unwrap();
+
+        assert_eq!(b.min, Point::new(-1.0, -4.0, 0.0));
+        assert_eq!(b.max, Point::new(3.0, 2.0, 7.0));
+        assert_eq!(Bounds::from_points([]), None);
+    }
+
+    #[test]
+    fn corners_span_the_box() {
+        let b = Bounds::new(Point::new(-1.0, -2.0, -3.0), Point::new(1.0, 2.0, 3.0));
+        let corners = b.corners();
+
+        assert_eq!(corners.len(), 8);
+        assert_eq!(Bounds::from_points(corners), Some(b));
+        for (i, a) in corners.iter().enumerate() {
+            assert!(corners[i + 1..].iter().all(|c| c != a));
+        }
+    }
+
+    #[test]
+    fn rotated_cube_widens_in_x_and_z() {
+        let cube = Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let rotated = cube.transform(Matrix::rotation_y(FRAC_PI_4));
+        let r = Float::sqrt(2.0);
+
+        assert_abs_diff_eq!(rotated.min, Point::new(-r, -1.0, -r));
+        assert_abs_diff_eq!(rotated.max, Point::new(r, 1.0, r));
+    }
+
+    #[test]
+    fn translated_bounds_move_with_the_transform() {
+        let b = Bounds::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 2.0, 3.0));
+        let moved = b.transform(Matrix::translation(1.0, -1.0, 2.0));
+
+        assert_eq!(moved.min, Point::new(1.0, -1.0, 2.0));
+        assert_eq!(moved.max, Point::new(2.0, 1.0, 5.0));
+        assert_eq!(moved.center(), Point::new(1.5, 0.0, 3.5));
+    }
+}
@@ -3,25 +3,81 @@ use crate::color::Color;
 use crate::matrix::Matrix;
 use crate::point::Point;
 use crate::ray::Ray;
-use crate::world::World;
+use crate::sampling::{ReconstructionFilter, Rng, SamplePattern};
+use crate::shapes::Shape;
+use crate::utils::Float;
+use crate::utils::consts::PI;
+use crate::vector::Vector;
+use crate::world::{ShadeComponents, World};
 use rayon::prelude::*;
+use std::fmt;
 use std::io::Write;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
-#[derive(Debug)]
+// Separate canvases for each kind of light, see `Camera::render_passes`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderPasses {
+    pub direct: Canvas,
+    pub reflected: Canvas,
+    pub refracted: Canvas,
+}
+
+// Reasons `Camera::try_new` can reject its inputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvalidCamera {
+    // hsize or vsize is zero
+    EmptyCanvas,
+    // the field of view must lie strictly between 0 and π
+    FieldOfView,
+}
+
+impl fmt::Display for InvalidCamera {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidCamera::EmptyCanvas => write!(f, "camera canvas has zero width or height"),
+            InvalidCamera::FieldOfView => write!(f, "camera field of view must be in (0, π)"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidCamera {}
+
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub hsize: usize,
     pub vsize: usize,
-    pub field_of_view: f64,
+    pub field_of_view: Float,
     pub transform: Matrix<4>,
-    pub pixel_size: f64,
-    half_width: f64,
-    half_height: f64,
+    pub pixel_size: Float,
+    // rays traced and averaged per pixel by the color renders; 1 = one ray
+    // through each pixel's center (no anti-aliasing), see `with_antialiasing`
+    pub samples: usize,
+    pub sample_pattern: SamplePattern,
+    pub filter: ReconstructionFilter,
+    half_width: Float,
+    half_height: Float,
 }
 
 impl Camera {
-    pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
+    // panics on a zero-sized canvas or a field of view outside (0, π); see `try_new`
+    pub fn new(hsize: usize, vsize: usize, field_of_view: Float) -> Self {
+        Self::try_new(hsize, vsize, field_of_view).expect("camera parameters should be valid")
+    }
+
+    pub fn try_new(
+        hsize: usize,
+        vsize: usize,
+        field_of_view: Float,
+    ) -> Result<Self, InvalidCamera> {
+        if hsize == 0 || vsize == 0 {
+            return Err(InvalidCamera::EmptyCanvas);
+        }
+        // tan(fov / 2) is 0 or unbounded at the ends of the range
+        if !(field_of_view > 0.0 && field_of_view < PI) {
+            return Err(InvalidCamera::FieldOfView);
+        }
+
         let transform = Matrix::identity();
 
         // the camera's canvas is always one unit in front of the camera
@@ -31,7 +87,7 @@ impl Camera {
         let half_view = (field_of_view / 2.0).tan();
 
         // depending on the aspect ration, half_view is either half the width or height
-        let aspect = hsize as f64 / vsize as f64;
+        let aspect = hsize as Float / vsize as Float;
         let half_width = if aspect >= 1.0 {
             half_view
         } else {
@@ -42,21 +98,194 @@ impl Camera {
         } else {
             half_view
         };
-        Self {
+        Ok(Self {
             hsize,
             vsize,
             field_of_view,
             transform,
-            pixel_size: half_width * 2.0 / hsize as f64,
+            pixel_size: half_width * 2.0 / hsize as Float,
+            samples: 1,
+            sample_pattern: SamplePattern::default(),
+            filter: ReconstructionFilter::default(),
             half_width,
             half_height,
+        })
+    }
+
+    // field of view (radians) of a lens of `focal_mm` on a sensor `sensor_mm` across
+    pub fn focal_length_to_fov(focal_mm: Float, sensor_mm: Float) -> Float {
+        2.0 * (sensor_mm / (2.0 * focal_mm)).atan()
+    }
+
+    // like `new`, but described as a lens; `sensor_mm` spans the longer side of
+    // the canvas, which is the side the field of view applies to
+    pub fn with_focal_length(
+        hsize: usize,
+        vsize: usize,
+        focal_mm: Float,
+        sensor_mm: Float,
+    ) -> Self {
+        Self::new(hsize, vsize, Self::focal_length_to_fov(focal_mm, sensor_mm))
+    }
+
+    // look at `target` from a point `radius` away on a sphere around it.
+    // Azimuth 0, elevation 0 is straight down -z from the target; azimuth turns
+    // the same way as `render_turntable`, and elevation raises the camera
+    // toward +y (keep it strictly between -π/2 and π/2, where up is defined)
+    pub fn orbit(&mut self, target: Point, radius: Float, azimuth: Float, elevation: Float) {
+        let offset = Vector::new(
+            -elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        self.transform =
+            Matrix::view_transform(target + offset * radius, target, Vector::new(0.0, 1.0, 0.0));
+    }
+
+    // trace `samples` rays per pixel, spread over it by `pattern`, and average them
+    pub fn with_antialiasing(mut self, samples: usize, pattern: SamplePattern) -> Self {
+        self.samples = samples.max(1);
+        self.sample_pattern = pattern;
+        self
+    }
+
+    // how the anti-aliasing samples of a pixel are weighted when averaged
+    pub fn with_filter(mut self, filter: ReconstructionFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    // compute the world-space ray from the camera through the center of pixel (px, py)
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_for_pixel_with(self.transform.inverse(), px, py)
+    }
+
+    // world-space spacing between the rays through the left and right edges
+    // of pixel (px, py), measured one unit along the rays; useful for picking
+    // a texture or geometry level of detail (multiply by hit distance)
+    pub fn pixel_footprint(&self, px: usize, py: usize) -> Float {
+        let camera_inverse = self.transform.inverse();
+        let (x, y) = (px as Float, py as Float + 0.5);
+
+        let a = self.ray_through(camera_inverse, x, y).direction;
+        let b = self.ray_through(camera_inverse, x + 1.0, y).direction;
+        (b - a).magnitude()
+    }
+
+    // allocation-free ray_for_pixel using a precomputed camera inverse
+    fn ray_for_pixel_with(&self, camera_inverse: Matrix<4>, px: usize, py: usize) -> Ray {
+        self.ray_through(camera_inverse, px as Float + 0.5, py as Float + 0.5)
+    }
+
+    // the color of pixel (px, py), averaged over the camera's samples
+    fn pixel_color(&self, world: &World, camera_inverse: Matrix<4>, px: usize, py: usize) -> Color {
+        if self.samples <= 1 {
+            return world.color_at(self.ray_for_pixel_with(camera_inverse, px, py), 5);
+        }
+
+        let samples = self.pixel_samples(camera_inverse, px, py, |ray| world.color_at(ray, 5));
+        resolve_samples(&samples, |&c| c)
+    }
+
+    // `f` evaluated along each of pixel (px, py)'s sample rays, paired with
+    // that sample's filter weight; one ray through the pixel center when the
+    // camera takes a single sample
+    fn pixel_samples<T>(
+        &self,
+        camera_inverse: Matrix<4>,
+        px: usize,
+        py: usize,
+        f: impl Fn(Ray) -> T,
+    ) -> Vec<(Float, T)> {
+        if self.samples <= 1 {
+            return vec![(1.0, f(self.ray_for_pixel_with(camera_inverse, px, py)))];
+        }
+
+        // seeded by the pixel, so renders are repeatable
+        let mut rng = Rng::new((px + py * self.hsize) as u64);
+        self.sample_pattern
+            .offsets(self.samples, &mut rng)
+            .into_iter()
+            .map(|(dx, dy)| {
+                let ray = self.ray_through(camera_inverse, px as Float + dx, py as Float + dy);
+                (self.filter.weight(dx - 0.5, dy - 0.5), f(ray))
+            })
+            .collect()
+    }
+
+    // the ray through canvas position (x, y), measured in pixels from the top left
+    fn ray_through(&self, camera_inverse: Matrix<4>, x: Float, y: Float) -> Ray {
+        // the offset from the edge of the canvas to the position
+        let xoffset = x * self.pixel_size;
+        let yoffset = y * self.pixel_size;
+
+        // the untransformed coordinates of the pixel in world space
+        // (the camera looks toward -z, so +x is to the *left*)
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+
+        let pixel = camera_inverse * Point::new(world_x, world_y, -1.0);
+        let origin = camera_inverse * Point::ORIGIN;
+        let direction = (pixel - origin).normalize();
+
+        Ray::new(origin, direction)
+    }
+
+    // the pixel rectangle (x, y, width, height) that `shape` can cover,
+    // clipped to the image: its object-space bounding box is projected through
+    // the camera. None if the shape is entirely behind the camera or off
+    // screen; the whole image if it is unbounded or reaches behind the camera.
+    pub fn screen_bounds(&self, shape: &Shape) -> Option<(usize, usize, usize, usize)> {
+        let full = Some((0, 0, self.hsize, self.vsize));
+        let Some(bounds) = shape.local_bounds() else {
+            return full;
+        };
+
+        let to_camera = self.transform * *shape.transform();
+        let corners = bounds.corners().map(|c| to_camera * c);
+        // the camera looks down -z; in front of it means z < 0
+        if corners.iter().all(|c| c.z >= 0.0) {
+            return None;
         }
+        if corners.iter().any(|c| c.z >= 0.0) {
+            return full;
+        }
+
+        // where each corner lands on the canvas at z = -1, in pixel units
+        let (mut x0, mut y0) = (Float::INFINITY, Float::INFINITY);
+        let (mut x1, mut y1) = (Float::NEG_INFINITY, Float::NEG_INFINITY);
+        for c in corners {
+            let px = (self.half_width - c.x / -c.z) / self.pixel_size;
+            let py = (self.half_height - c.y / -c.z) / self.pixel_size;
+            (x0, x1) = (x0.min(px), x1.max(px));
+            (y0, y1) = (y0.min(py), y1.max(py));
+        }
+
+        let clip = |v: Float, size: usize| v.clamp(0.0, size as Float) as usize;
+        let (x0, x1) = (clip(x0.floor(), self.hsize), clip(x1.ceil(), self.hsize));
+        let (y0, y1) = (clip(y0.floor(), self.vsize), clip(y1.ceil(), self.vsize));
+        if x0 == x1 || y0 == y1 {
+            return None;
+        }
+        Some((x0, y0, x1 - x0, y1 - y0))
+    }
+
+    // generate every primary ray in row-major order (index = x + y * hsize)
+    // so callers can feed them to a custom intersector
+    pub fn primary_rays(&self) -> Vec<Ray> {
+        let camera_inverse = self.transform.inverse();
+
+        (0..self.hsize * self.vsize)
+            .map(|i| self.ray_for_pixel_with(camera_inverse, i % self.hsize, i / self.hsize))
+            .collect()
     }
 
     pub fn render(&self, world: &World) -> Canvas
     where
         World: Sync,
     {
+        world.prepare();
+
         let w = self.hsize;
         let h = self.vsize;
         let total = w * h;
@@ -70,18 +299,7 @@ impl Camera {
                 let x = i % w;
                 let y = i / w;
 
-                // Inline, allocation-free ray_for_pixel using precomputed camera_inverse
-                let xoffset = (x as f64 + 0.5) * self.pixel_size;
-                let yoffset = (y as f64 + 0.5) * self.pixel_size;
-                let world_x = self.half_width - xoffset;
-                let world_y = self.half_height - yoffset;
-
-                let pixel = camera_inverse * Point::new(world_x, world_y, -1.0);
-                let origin = camera_inverse * Point::ORIGIN;
-                let direction = (pixel - origin).normalize();
-                let ray = Ray::new(origin, direction);
-
-                world.color_at(ray, 5)
+                self.pixel_color(world, camera_inverse, x, y)
             })
             .collect();
 
@@ -94,10 +312,194 @@ impl Camera {
         }
     }
 
+    // render the single image row `y`, left to right, so callers can stream
+    // rows to a display or file as they finish
+    pub fn render_scanline(&self, world: &World, y: usize) -> Vec<Color> {
+        world.prepare();
+        let camera_inverse = self.transform.inverse();
+
+        (0..self.hsize)
+            .into_par_iter()
+            .map(|x| self.pixel_color(world, camera_inverse, x, y))
+            .collect()
+    }
+
+    // render color plus a depth buffer holding, per pixel in row-major order,
+    // the distance to the first hit averaged over the samples that hit
+    // something (infinity where none do)
+    pub fn render_with_depth(&self, world: &World) -> (Canvas, Vec<Float>) {
+        world.prepare();
+        let camera_inverse = self.transform.inverse();
+
+        let (pixels, depth): (Vec<Color>, Vec<Float>) = (0..self.hsize * self.vsize)
+            .into_par_iter()
+            .map(|i| {
+                let samples =
+                    self.pixel_samples(camera_inverse, i % self.hsize, i / self.hsize, |ray| {
+                        let depth = world
+                            .hit_distance(ray)
+                            .map(|t| t * ray.direction.magnitude());
+                        (world.color_at(ray, 5), depth)
+                    });
+
+                let (mut sum, mut weight) = (0.0, 0.0);
+                for &(w, (_, depth)) in &samples {
+                    if let Some(depth) = depth {
+                        sum += depth * w;
+                        weight += w;
+                    }
+                }
+                let depth = if weight > 0.0 {
+                    sum / weight
+                } else {
+                    Float::INFINITY
+                };
+                (resolve_samples(&samples, |&(c, _)| c), depth)
+            })
+            .unzip();
+
+        (Canvas::from_pixels(self.hsize, self.vsize, pixels), depth)
+    }
+
+    // render with coverage for compositing: per pixel in row-major order,
+    // premultiplied [r, g, b, a] where alpha is the filter-weighted fraction of
+    // samples that hit something; samples that see the environment count as
+    // fully transparent
+    pub fn render_rgba(&self, world: &World) -> Vec<[Float; 4]> {
+        world.prepare();
+        let camera_inverse = self.transform.inverse();
+
+        (0..self.hsize * self.vsize)
+            .into_par_iter()
+            .map(|i| {
+                let samples =
+                    self.pixel_samples(camera_inverse, i % self.hsize, i / self.hsize, |ray| {
+                        world.hit_distance(ray).map(|_| world.color_at(ray, 5))
+                    });
+
+                let c = resolve_samples(&samples, |c| c.unwrap_or(Color::BLACK));
+                let total: Float = samples.iter().map(|&(w, _)| w).sum();
+                let covered: Float = samples
+                    .iter()
+                    .filter(|(_, c)| c.is_some())
+                    .map(|&(w, _)| w)
+                    .sum();
+                let alpha = if total > 0.0 { covered / total } else { 0.0 };
+                [c.red, c.green, c.blue, alpha]
+            })
+            .collect()
+    }
+
+    // per pixel in row-major order, the index in `world.objects` of the shape
+    // the primary ray hits first (None where the ray hits nothing)
+    pub fn render_object_ids(&self, world: &World) -> Vec<Option<usize>> {
+        let camera_inverse = self.transform.inverse();
+
+        (0..self.hsize * self.vsize)
+            .into_par_iter()
+            .map(|i| {
+                let ray = self.ray_for_pixel_with(camera_inverse, i % self.hsize, i / self.hsize);
+                let hits = world.intersections(ray);
+                let hit = hits.hit()?;
+                world.objects.iter().position(|o| std::ptr::eq(o, hit.s))
+            })
+            .collect()
+    }
+
+    // re-render only the pixels in `dirty_rect` = (x, y, width, height),
+    // copying the rest from `prev`; the rectangle is clipped to the canvas
+    pub fn render_dirty(
+        &self,
+        world: &World,
+        prev: &Canvas,
+        dirty_rect: (usize, usize, usize, usize),
+    ) -> Canvas {
+        world.prepare();
+
+        let (x0, y0, w, h) = dirty_rect;
+        let x1 = (x0 + w).min(self.hsize);
+        let y1 = (y0 + h).min(self.vsize);
+        let camera_inverse = self.transform.inverse();
+
+        let dirty: Vec<(usize, usize)> = (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+            .collect();
+        let colors: Vec<Color> = dirty
+            .par_iter()
+            .map(|&(x, y)| self.pixel_color(world, camera_inverse, x, y))
+            .collect();
+
+        let mut canvas = prev.clone();
+        for (&(x, y), color) in dirty.iter().zip(colors) {
+            canvas.write_pixel(x, y, color);
+        }
+        canvas
+    }
+
+    // render direct, reflected and refracted light into separate canvases
+    // for compositing; each pass is filtered like `render`, so the three add
+    // up to the `render` image
+    pub fn render_passes(&self, world: &World) -> RenderPasses {
+        world.prepare();
+        let camera_inverse = self.transform.inverse();
+
+        let components: Vec<ShadeComponents> = (0..self.hsize * self.vsize)
+            .into_par_iter()
+            .map(|i| {
+                let samples =
+                    self.pixel_samples(camera_inverse, i % self.hsize, i / self.hsize, |ray| {
+                        world.color_components_at(ray, 5)
+                    });
+                ShadeComponents {
+                    direct: resolve_samples(&samples, |c| c.direct),
+                    reflected: resolve_samples(&samples, |c| c.reflected),
+                    refracted: resolve_samples(&samples, |c| c.refracted),
+                }
+            })
+            .collect();
+
+        let pass = |f: fn(&ShadeComponents) -> Color| {
+            Canvas::from_pixels(self.hsize, self.vsize, components.iter().map(f).collect())
+        };
+
+        RenderPasses {
+            direct: pass(|c| c.direct),
+            reflected: pass(|c| c.reflected),
+            refracted: pass(|c| c.refracted),
+        }
+    }
+
+    // render one frame per world produced by `world_fn(frame)`
+    pub fn render_sequence(&self, world_fn: impl Fn(usize) -> World, frames: usize) -> Vec<Canvas> {
+        (0..frames)
+            .map(|frame| self.render(&world_fn(frame)))
+            .collect()
+    }
+
+    // render a turntable: the camera orbits `center` about the y axis,
+    // advancing 2π / frames between consecutive frames
+    pub fn render_turntable(&self, world: &World, center: Point, frames: usize) -> Vec<Canvas> {
+        let step = 2.0 * crate::utils::consts::PI / frames as Float;
+
+        (0..frames)
+            .map(|frame| {
+                // orbiting the camera by +angle is the same as spinning the world by -angle
+                let orbit = Matrix::translation(center.x, center.y, center.z)
+                    * Matrix::rotation_y(-step * frame as Float)
+                    * Matrix::translation(-center.x, -center.y, -center.z);
+                let mut camera = self.clone();
+                camera.transform = self.transform * orbit;
+                camera.render(world)
+            })
+            .collect()
+    }
+
     pub fn render_with_progress(&self, world: &World) -> Canvas
     where
         World: Sync,
     {
+        world.prepare();
+
         let w = self.hsize;
         let h = self.vsize;
         let total = w * h;
@@ -116,23 +518,12 @@ impl Camera {
                 let x = i % w;
                 let y = i / w;
 
-                // Inline, allocation-free ray_for_pixel using precomputed camera_inverse
-                let xoffset = (x as f64 + 0.5) * self.pixel_size;
-                let yoffset = (y as f64 + 0.5) * self.pixel_size;
-                let world_x = self.half_width - xoffset;
-                let world_y = self.half_height - yoffset;
-
-                let pixel = camera_inverse * Point::new(world_x, world_y, -1.0);
-                let origin = camera_inverse * Point::ORIGIN;
-                let direction = (pixel - origin).normalize();
-                let ray = Ray::new(origin, direction);
-
-                let c = world.color_at(ray, 5);
+                let c = self.pixel_color(world, camera_inverse, x, y);
 
                 // progress display
                 let n = progress.fetch_add(1, Ordering::Relaxed) + 1;
-                if n % step == 0 || n == total {
-                    let pct = 100.0 * (n as f64) / (total as f64);
+                if n.is_multiple_of(step) || n == total {
+                    let pct = 100.0 * (n as Float) / (total as Float);
                     let elapsed = start.elapsed();
                     // \r returns to line start, flush forces immediate update
                     print!("\rProgress: {pct:>6.2}%  Elapsed Time: {elapsed:?}");
@@ -146,16 +537,78 @@ impl Camera {
         eprintln!("\nDone in {:?}", start.elapsed());
         Canvas::from_pixels(w, h, pixels)
     }
+
+    // Render in passes that double the samples per pixel (1, 2, 4, ...) until
+    // the camera's `samples` are reached, the last pass topping up to exactly
+    // that many, calling `on_frame` with the running average after each pass
+    // so a UI can show the image sharpening.
+    // Returns the final frame.
+    pub fn render_progressive(&self, world: &World, on_frame: impl Fn(&Canvas)) -> Canvas {
+        world.prepare();
+        let camera_inverse = self.transform.inverse();
+        // per pixel: filter-weighted color sum and total weight so far
+        let mut sums = vec![(Color::BLACK, 0.0); self.hsize * self.vsize];
+        let mut taken = 0;
+        let mut pass = 0u64;
+
+        loop {
+            let count = (1 << pass).min(self.samples.max(1)) - taken;
+
+            sums.par_iter_mut()
+                .enumerate()
+                .for_each(|(i, (color, weight))| {
+                    let (px, py) = (i % self.hsize, i / self.hsize);
+                    // seeded by pixel and pass, so renders are repeatable but
+                    // every pass adds new sample positions
+                    let mut rng = Rng::new(((i as u64) << 6) | pass);
+                    for (dx, dy) in self.sample_pattern.offsets(count, &mut rng) {
+                        let ray =
+                            self.ray_through(camera_inverse, px as Float + dx, py as Float + dy);
+                        let w = self.filter.weight(dx - 0.5, dy - 0.5);
+                        *color = *color + world.color_at(ray, 5) * w;
+                        *weight += w;
+                    }
+                });
+            taken += count;
+
+            let pixels = sums
+                .iter()
+                .map(|&(color, weight)| {
+                    if weight > 0.0 {
+                        color * (1.0 / weight)
+                    } else {
+                        Color::BLACK
+                    }
+                })
+                .collect();
+            let frame = Canvas::from_pixels(self.hsize, self.vsize, pixels);
+            on_frame(&frame);
+
+            if taken >= self.samples {
+                return frame;
+            }
+            pass += 1;
+        }
+    }
+}
+
+// filter-weighted average of the color `f` picks out of each pixel sample
+fn resolve_samples<T>(samples: &[(Float, T)], f: impl Fn(&T) -> Color) -> Color {
+    let (weights, colors): (Vec<Float>, Vec<Color>) =
+        samples.iter().map(|(w, sample)| (*w, f(sample))).unzip();
+    Color::mix_many(&colors, &weights)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::color::Color;
+    use crate::material::Material;
     use crate::point::Point;
-    use crate::vector::Vector;
+    use crate::point_light::PointLight;
+    use crate::utils::ROUNDING_EPSILON;
+    use crate::utils::consts::{PI, SQRT_2};
     use approx::assert_abs_diff_eq;
-    use std::f64::consts::PI;
 
     #[test]
     fn create_camera() {
@@ -180,6 +633,131 @@ mod tests {
         assert_abs_diff_eq!(camera.pixel_size, 0.01);
     }
 
+    #[test]
+    fn ray_through_center_of_canvas() {
+        let camera = Camera::new(201, 101, PI / 2.0);
+        let r = camera.ray_for_pixel(100, 50);
+
+        assert_abs_diff_eq!(r.origin, Point::ORIGIN);
+        assert_abs_diff_eq!(r.direction, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn ray_through_corner_of_canvas() {
+        let camera = Camera::new(201, 101, PI / 2.0);
+        let r = camera.ray_for_pixel(0, 0);
+
+        assert_abs_diff_eq!(r.origin, Point::ORIGIN);
+        assert_abs_diff_eq!(r.direction, Vector::new(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn ray_when_camera_is_transformed() {
+        let mut camera = Camera::new(201, 101, PI / 2.0);
+        camera.transform = Matrix::rotation_y(PI / 4.0) * Matrix::translation(0.0, -2.0, 5.0);
+        let r = camera.ray_for_pixel(100, 50);
+
+        assert_abs_diff_eq!(r.origin, Point::new(0.0, 2.0, -5.0));
+        assert_abs_diff_eq!(
+            r.direction,
+            Vector::new(Float::sqrt(2.0) / 2.0, 0.0, -Float::sqrt(2.0) / 2.0)
+        );
+    }
+
+    #[test]
+    fn primary_rays_cover_every_pixel() {
+        let mut camera = Camera::new(21, 11, PI / 2.0);
+        camera.transform = Matrix::rotation_y(PI / 4.0) * Matrix::translation(0.0, -2.0, 5.0);
+        let rays = camera.primary_rays();
+
+        assert_eq!(rays.len(), camera.hsize * camera.vsize);
+
+        let (x, y) = (7, 4);
+        let expected = camera.ray_for_pixel(x, y);
+        let actual = rays[x + y * camera.hsize];
+        assert_abs_diff_eq!(actual.origin, expected.origin);
+        assert_abs_diff_eq!(actual.direction, expected.direction);
+    }
+
+    #[test]
+    fn turntable_frames_differ_pairwise() {
+        let w = World::default();
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let frames = camera.render_turntable(&w, Point::ORIGIN, 4);
+
+        assert_eq!(frames.len(), 4);
+        for i in 0..4 {
+            for j in i + 1..4 {
+                assert_ne!(frames[i].pixels, frames[j].pixels, "frames {i} and {j}");
+            }
+        }
+    }
+
+    #[test]
+    fn orbit_places_the_camera_on_a_sphere_around_the_target() {
+        let target = Point::new(1.0, 2.0, 3.0);
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+
+        camera.orbit(target, 5.0, 0.0, 0.0);
+        assert_abs_diff_eq!(
+            camera.transform,
+            Matrix::view_transform(
+                target + Vector::new(0.0, 0.0, -5.0),
+                target,
+                Vector::new(0.0, 1.0, 0.0),
+            )
+        );
+
+        // the ray through the center pixel starts on the sphere and hits the target
+        let eye = |c: &Camera| c.ray_for_pixel(5, 5);
+        camera.orbit(target, 5.0, PI / 2.0, PI / 4.0);
+        let r = eye(&camera);
+        let expected = Vector::new(-5.0 / Float::sqrt(2.0), 5.0 / Float::sqrt(2.0), 0.0);
+        assert_abs_diff_eq!(r.origin - target, expected, epsilon = ROUNDING_EPSILON);
+        assert_abs_diff_eq!(r.position(5.0), target, epsilon = ROUNDING_EPSILON);
+    }
+
+    #[test]
+    fn turntable_first_frame_matches_render() {
+        let w = World::default();
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let frames = camera.render_turntable(&w, Point::ORIGIN, 3);
+
+        assert_eq!(frames[0], camera.render(&w));
+    }
+
+    #[test]
+    fn render_sequence_renders_each_world() {
+        let mut camera = Camera::new(5, 5, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let frames = camera.render_sequence(
+            |frame| {
+                let mut w = World::default();
+                w.objects[0].material_mut().color = Color::new(0.2 * frame as Float, 0.5, 0.5);
+                w
+            },
+            3,
+        );
+
+        assert_eq!(frames.len(), 3);
+        assert_ne!(frames[0].pixel_at(2, 2), frames[1].pixel_at(2, 2));
+        assert_ne!(frames[1].pixel_at(2, 2), frames[2].pixel_at(2, 2));
+    }
+
     #[test]
     fn rendering_world_with_camera() {
         let w = World::default();
@@ -192,4 +770,433 @@ mod tests {
         let image = camera.render(&w);
         assert_abs_diff_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn render_passes_sum_to_the_render() {
+        use crate::material::Material;
+        use crate::shapes::{Plane, Shape};
+
+        let mut w = World::default().with_background(Color::new(0.2, 0.3, 0.5));
+        w.objects.push(
+            Shape::from(Plane::new())
+                .with_material(
+                    Material::new()
+                        .with_reflective(0.5)
+                        .with_transparency(0.5)
+                        .with_refractive_index(1.5),
+                )
+                .with_transform(Matrix::translation(0.0, -1.0, 0.0)),
+        );
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 1.5, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let antialiased = camera
+            .clone()
+            .with_antialiasing(4, SamplePattern::Halton)
+            .with_filter(ReconstructionFilter::Tent);
+
+        for camera in [camera, antialiased] {
+            let image = camera.render(&w);
+            let passes = camera.render_passes(&w);
+
+            assert!(passes.reflected.pixels.iter().any(|&c| c != Color::BLACK));
+            assert!(passes.refracted.pixels.iter().any(|&c| c != Color::BLACK));
+            for i in 0..image.pixels.len() {
+                let sum = passes.direct.pixels[i]
+                    + passes.reflected.pixels[i]
+                    + passes.refracted.pixels[i];
+                assert_abs_diff_eq!(sum, image.pixels[i], epsilon = ROUNDING_EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn antialiasing_blends_silhouette_pixels() {
+        // a flat white disc on black, so each pixel's value is its coverage
+        let flat = Material {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        };
+        let world = World::new(
+            vec![Shape::sphere().with_material(flat)],
+            PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE),
+        );
+        let mut camera = Camera::new(15, 15, PI / 3.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let is_partial = |c: &Color| c.red > 0.0 && c.red < 1.0;
+
+        let aliased = camera.render(&world);
+        assert!(!aliased.pixels.iter().any(is_partial));
+
+        for pattern in [
+            SamplePattern::Grid,
+            SamplePattern::Halton,
+            SamplePattern::BlueNoise,
+        ] {
+            let smooth = camera.clone().with_antialiasing(16, pattern).render(&world);
+            assert!(smooth.pixels.iter().any(is_partial), "{pattern:?}");
+            // well inside and well outside the disc nothing changes
+            assert_eq!(smooth.pixel_at(7, 7), Color::WHITE);
+            assert_eq!(smooth.pixel_at(0, 0), Color::BLACK);
+        }
+    }
+
+    #[test]
+    fn antialiased_depth_and_coverage_match_the_render() {
+        // a flat white disc on black, so each pixel's value is its coverage
+        let flat = Material {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        };
+        let world = World::new(
+            vec![Shape::sphere().with_material(flat)],
+            PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE),
+        );
+        let mut camera = Camera::new(15, 15, PI / 3.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let camera = camera.with_antialiasing(16, SamplePattern::Grid);
+
+        let image = camera.render(&world);
+        let (depth_image, depth) = camera.render_with_depth(&world);
+        let rgba = camera.render_rgba(&world);
+
+        assert_eq!(depth_image, image);
+        assert!(rgba.iter().any(|&[.., a]| a > 0.0 && a < 1.0));
+        for (i, &[r, g, b, a]) in rgba.iter().enumerate() {
+            assert_abs_diff_eq!(
+                Color::new(r, g, b),
+                image.pixels[i],
+                epsilon = ROUNDING_EPSILON
+            );
+            assert_abs_diff_eq!(a, image.pixels[i].red, epsilon = ROUNDING_EPSILON);
+            assert_eq!(depth[i].is_finite(), a > 0.0);
+        }
+    }
+
+    #[test]
+    fn scanlines_assemble_into_the_full_render() {
+        let w = World::default();
+        let mut camera = Camera::new(9, 7, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let rows: Vec<Color> = (0..camera.vsize)
+            .flat_map(|y| camera.render_scanline(&w, y))
+            .collect();
+
+        assert_eq!(rows, camera.render(&w).pixels);
+    }
+
+    #[test]
+    fn screen_bounds_of_a_centered_sphere() {
+        let mut camera = Camera::new(101, 101, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let sphere = Shape::sphere();
+
+        let (x, y, w, h) = camera.screen_bounds(&sphere).unwrap();
+        assert!(w < 101 && h < 101);
+        assert_eq!((x, y), (101 - (x + w), 101 - (y + h)));
+
+        // every pixel the sphere covers is inside the rectangle
+        let world = World::new(
+            vec![sphere.clone()],
+            PointLight::new(Point::ORIGIN, Color::WHITE),
+        );
+        let ids = camera.render_object_ids(&world);
+        for (i, id) in ids.iter().enumerate() {
+            if id.is_some() {
+                let (px, py) = (i % 101, i / 101);
+                assert!((x..x + w).contains(&px) && (y..y + h).contains(&py));
+            }
+        }
+
+        let behind = sphere.with_transform(Matrix::translation(0.0, 0.0, -10.0));
+        assert_eq!(camera.screen_bounds(&behind), None);
+        assert_eq!(
+            camera.screen_bounds(&Shape::plane()),
+            Some((0, 0, 101, 101))
+        );
+    }
+
+    #[test]
+    fn render_dirty_only_touches_the_rectangle() {
+        let w = World::default();
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let mut prev = Canvas::empty(11, 11);
+        prev.clear(Color::RED);
+
+        let full = camera.render(&w);
+        let updated = camera.render_dirty(&w, &prev, (4, 5, 3, 20));
+
+        for y in 0..11 {
+            for x in 0..11 {
+                let inside = (4..7).contains(&x) && y >= 5;
+                let expected = if inside {
+                    full.pixel_at(x, y)
+                } else {
+                    prev.pixel_at(x, y)
+                };
+                assert_eq!(updated.pixel_at(x, y), expected);
+            }
+        }
+    }
+
+    // the book's glass floor scene, rendered small; catches regressions in
+    // refracted_color and schlick
+    #[test]
+    fn glass_floor_render_regression() {
+        use crate::material::Material;
+        use crate::shapes::{Plane, Shape, Sphere};
+
+        let mut w = World::default();
+        w.objects.push(
+            Shape::from(Plane::new())
+                .with_transform(Matrix::translation(0.0, -1.0, 0.0))
+                .with_material(
+                    Material::new()
+                        .with_reflective(0.5)
+                        .with_transparency(0.5)
+                        .with_refractive_index(1.5),
+                ),
+        );
+        w.objects.push(
+            Shape::from(Sphere::new())
+                .with_transform(Matrix::translation(0.0, -3.5, -0.5))
+                .with_material(Material::new().with_color(Color::RED).with_ambient(0.5)),
+        );
+        let mut camera = Camera::new(21, 21, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 1.0, -3.0),
+            Point::new(0.0, -1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let image = camera.render(&w);
+
+        // three pixels see the red ball through the floor, one only the floor
+        let expected = [
+            ((10, 12), Color::new(0.91298, 0.67381, 0.67289)),
+            ((11, 15), Color::new(0.92188, 0.68189, 0.68189)),
+            ((8, 14), Color::new(0.93237, 0.69240, 0.69240)),
+            ((3, 18), Color::new(0.72085, 0.72085, 0.72085)),
+        ];
+        for ((x, y), color) in expected {
+            assert_abs_diff_eq!(image.pixel_at(x, y), color, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn depth_buffer_holds_distance_to_the_first_hit() {
+        use crate::point_light::PointLight;
+        use crate::shapes::Shape;
+
+        // a near sphere on the left, a far one on the right
+        let w = World::new(
+            vec![
+                Shape::sphere().with_transform(Matrix::translation(-2.0, 0.0, 0.0)),
+                Shape::sphere().with_transform(Matrix::translation(2.0, 0.0, 10.0)),
+            ],
+            PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE),
+        );
+        let mut camera = Camera::new(21, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let (image, depth) = camera.render_with_depth(&w);
+        let depth_at = |x: usize, y: usize| depth[x + y * camera.hsize];
+
+        assert_eq!(depth.len(), image.pixels.len());
+        assert!(depth_at(0, 0).is_infinite());
+        let (near, far) = (depth_at(6, 5), depth_at(11, 5));
+        assert!(near.is_finite() && far.is_finite());
+        assert!(near < far);
+    }
+
+    #[test]
+    fn rgba_render_is_transparent_where_rays_miss() {
+        let w = World::default().with_background(Color::new(0.2, 0.3, 0.5));
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let rgba = camera.render_rgba(&w);
+
+        assert_eq!(rgba.len(), 11 * 11);
+        assert_eq!(rgba[0], [0.0; 4]);
+        let [r, g, b, a] = rgba[5 + 5 * 11];
+        assert_abs_diff_eq!(Color::new(r, g, b), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(a, 1.0);
+    }
+
+    #[test]
+    fn object_ids_name_the_first_shape_hit() {
+        let w = World::default();
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let ids = camera.render_object_ids(&w);
+
+        assert_eq!(ids.len(), 11 * 11);
+        // the outer sphere hides the inner one
+        assert_eq!(ids[5 + 5 * 11], Some(0));
+        assert_eq!(ids[0], None);
+    }
+
+    #[test]
+    fn fifty_mm_lens_on_full_frame() {
+        let fov = Camera::focal_length_to_fov(50.0, 36.0);
+        assert_abs_diff_eq!(fov.to_degrees(), 39.5978, epsilon = 1e-4);
+
+        let c = Camera::with_focal_length(160, 120, 50.0, 36.0);
+        assert_abs_diff_eq!(c.field_of_view, fov);
+        assert_abs_diff_eq!(c.half_width, 18.0 / 50.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn degenerate_cameras_are_rejected() {
+        assert_eq!(
+            Camera::try_new(160, 0, PI / 2.0).unwrap_err(),
+            InvalidCamera::EmptyCanvas
+        );
+        assert_eq!(
+            Camera::try_new(0, 120, PI / 2.0).unwrap_err(),
+            InvalidCamera::EmptyCanvas
+        );
+        assert_eq!(
+            Camera::try_new(160, 120, PI).unwrap_err(),
+            InvalidCamera::FieldOfView
+        );
+        assert_eq!(
+            Camera::try_new(160, 120, 0.0).unwrap_err(),
+            InvalidCamera::FieldOfView
+        );
+        assert_eq!(
+            Camera::try_new(160, 120, Float::NAN).unwrap_err(),
+            InvalidCamera::FieldOfView
+        );
+        assert!(Camera::try_new(160, 120, PI / 2.0).is_ok());
+    }
+
+    #[test]
+    fn center_pixel_footprint_matches_pixel_size() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        assert_abs_diff_eq!(c.pixel_footprint(100, 50), c.pixel_size, epsilon = 1e-6);
+
+        // rays spread less per pixel toward the edges of a wide field of view
+        assert!(c.pixel_footprint(0, 0) < c.pixel_footprint(100, 50));
+
+        let mut moved = c.clone();
+        moved.transform = Matrix::rotation_y(0.5) * Matrix::translation(0.0, 0.0, -5.0);
+        assert_abs_diff_eq!(
+            moved.pixel_footprint(100, 50),
+            c.pixel_footprint(100, 50),
+            epsilon = ROUNDING_EPSILON
+        );
+
+        // a single-pixel-wide image still has a footprint
+        let single = Camera::new(1, 1, PI / 2.0);
+        assert_abs_diff_eq!(single.pixel_footprint(0, 0), SQRT_2, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn progressive_render_converges_to_the_antialiased_render() {
+        // a flat white disc on black, so each pixel's value is its coverage
+        let flat = Material {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        };
+        let world = World::new(
+            vec![Shape::sphere().with_material(flat)],
+            PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE),
+        );
+        let mut camera = Camera::new(15, 15, PI / 3.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let camera = camera.with_antialiasing(256, SamplePattern::Grid);
+
+        let frames = std::sync::Mutex::new(Vec::new());
+        let last = camera.render_progressive(&world, |frame| {
+            frames.lock().unwrap().push(frame.clone());
+        });
+        let frames = frames.into_inner().unwrap();
+        let reference = camera.render(&world);
+
+        // 1, 2, 4, ..., 256 samples
+        assert_eq!(frames.len(), 9);
+        assert_eq!(frames.last(), Some(&last));
+
+        let error = |frame: &Canvas| {
+            frame
+                .pixels
+                .iter()
+                .zip(&reference.pixels)
+                .map(|(a, b)| (a.red - b.red).abs())
+                .sum::<Float>()
+                / reference.pixels.len() as Float
+        };
+        assert!(error(&frames[0]) > error(&last));
+        assert!(error(&last) < 0.01, "{}", error(&last));
+        for (a, b) in last.pixels.iter().zip(&reference.pixels) {
+            assert_abs_diff_eq!(a.red, b.red, epsilon = 0.1);
+        }
+    }
+
+    #[test]
+    fn progressive_render_stops_at_the_requested_sample_count() {
+        // unlit, so every ray cast is a camera sample
+        let mut world = World::empty().with_render_stats();
+        world.objects.push(Shape::sphere());
+        let camera = Camera::new(4, 3, PI / 3.0).with_antialiasing(10, SamplePattern::Grid);
+
+        let frames = std::sync::atomic::AtomicUsize::new(0);
+        camera.render_progressive(&world, |_| {
+            frames.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+
+        // 1, 2, 4, 8, then 10 samples
+        assert_eq!(frames.into_inner(), 5);
+        assert_eq!(world.render_stats.unwrap().rays(), 4 * 3 * 10);
+    }
 }
@@ -1,7 +1,20 @@
 use std::fs;
 
+use rayon::prelude::*;
+
 use crate::color::Color;
+use crate::utils::Float;
+
+// Render settings embedded in a PPM as `#` comment lines, so an archived
+// image records how it was made; see `Canvas::to_ppm_with_metadata`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PpmMetadata {
+    pub samples: Option<usize>,
+    pub seed: Option<u64>,
+    pub scene_checksum: Option<u64>,
+}
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -33,6 +46,125 @@ impl Canvas {
         self.pixels[x + y * self.width]
     }
 
+    // fill the whole canvas with a single color
+    pub fn clear(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    // draw a line between two pixel coordinates using Bresenham's algorithm
+    // endpoints may lie off the canvas; the segment is clipped to the canvas
+    // first, so only visible pixels are stepped through
+    pub fn draw_line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, color: Color) {
+        let Some((x0, y0, x1, y1)) = self.clip_line(x0, y0, x1, y1) else {
+            return;
+        };
+
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        let (mut x, mut y) = (x0, y0);
+        loop {
+            self.write_pixel(x as usize, y as usize, color);
+
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    // Cohen-Sutherland clip of a segment to the canvas's pixel bounds; None if
+    // no part of it is visible. Done in f64 (not `Float`) so endpoints anywhere
+    // in the isize range can't overflow, and each clipped endpoint lands exactly
+    // on the edge it was clipped against
+    fn clip_line(
+        &self,
+        x0: isize,
+        y0: isize,
+        x1: isize,
+        y1: isize,
+    ) -> Option<(isize, isize, isize, isize)> {
+        const LEFT: u8 = 1;
+        const RIGHT: u8 = 2;
+        const TOP: u8 = 4;
+        const BOTTOM: u8 = 8;
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let (x_max, y_max) = ((self.width - 1) as f64, (self.height - 1) as f64);
+        // anything that rounds onto the canvas counts as inside
+        let outcode = |x: f64, y: f64| {
+            let mut code = 0;
+            if x < -0.5 {
+                code |= LEFT;
+            } else if x > x_max + 0.5 {
+                code |= RIGHT;
+            }
+            if y < -0.5 {
+                code |= TOP;
+            } else if y > y_max + 0.5 {
+                code |= BOTTOM;
+            }
+            code
+        };
+
+        let (mut x0, mut y0) = (x0 as f64, y0 as f64);
+        let (mut x1, mut y1) = (x1 as f64, y1 as f64);
+        let (mut c0, mut c1) = (outcode(x0, y0), outcode(x1, y1));
+
+        // each endpoint needs at most two clips (one per axis)
+        for _ in 0..4 {
+            if c0 | c1 == 0 {
+                break;
+            }
+            if c0 & c1 != 0 {
+                return None;
+            }
+
+            let code = if c0 != 0 { c0 } else { c1 };
+            let (x, y) = if code & LEFT != 0 {
+                (0.0, y0 + (y1 - y0) * (-x0 / (x1 - x0)))
+            } else if code & RIGHT != 0 {
+                (x_max, y0 + (y1 - y0) * ((x_max - x0) / (x1 - x0)))
+            } else if code & TOP != 0 {
+                (x0 + (x1 - x0) * (-y0 / (y1 - y0)), 0.0)
+            } else {
+                (x0 + (x1 - x0) * ((y_max - y0) / (y1 - y0)), y_max)
+            };
+
+            if code == c0 {
+                (x0, y0, c0) = (x, y, outcode(x, y));
+            } else {
+                (x1, y1, c1) = (x, y, outcode(x, y));
+            }
+        }
+        if c0 & c1 != 0 {
+            return None;
+        }
+
+        let pixel = |x: f64, y: f64| {
+            let x = x.round().clamp(0.0, x_max) as isize;
+            let y = y.round().clamp(0.0, y_max) as isize;
+            (x, y)
+        };
+        let ((x0, y0), (x1, y1)) = (pixel(x0, y0), pixel(x1, y1));
+        Some((x0, y0, x1, y1))
+    }
+
     pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>) -> Self {
         debug_assert_eq!(pixels.len(), width * height);
         Self {
@@ -42,7 +174,139 @@ impl Canvas {
         }
     }
 
+    // build a canvas by evaluating `f(x, y)` for every pixel, in parallel
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> Color + Sync) -> Self {
+        let pixels = (0..width * height)
+            .into_par_iter()
+            .map(|i| f(i % width, i / width))
+            .collect();
+
+        Self::from_pixels(width, height, pixels)
+    }
+
+    // darken toward the corners: each pixel is scaled by 1 - strength * r^2, where r is
+    // the distance of its center from the image center over the half-diagonal
+    pub fn vignette(&self, strength: Float) -> Canvas {
+        let (cx, cy) = (self.width as Float / 2.0, self.height as Float / 2.0);
+        let half_diagonal = cx.hypot(cy);
+
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            let r = (x as Float + 0.5 - cx).hypot(y as Float + 0.5 - cy) / half_diagonal;
+            self.pixel_at(x, y) * (1.0 - strength * r * r).max(0.0)
+        })
+    }
+
+    // each color channel as its own grayscale canvas: (red, green, blue)
+    pub fn split_channels(&self) -> (Canvas, Canvas, Canvas) {
+        let channel = |f: fn(&Color) -> Float| {
+            let pixels = self
+                .pixels
+                .iter()
+                .map(|c| {
+                    let v = f(c);
+                    Color::new(v, v, v)
+                })
+                .collect();
+            Canvas::from_pixels(self.width, self.height, pixels)
+        };
+
+        (
+            channel(|c| c.red),
+            channel(|c| c.green),
+            channel(|c| c.blue),
+        )
+    }
+
+    // mean luminance over all pixels; 0 for an empty canvas
+    pub fn average_luminance(&self) -> Float {
+        if self.pixels.is_empty() {
+            return 0.0;
+        }
+        self.pixels.iter().map(|c| c.luminance()).sum::<Float>() / self.pixels.len() as Float
+    }
+
+    // pixel counts over `bins` equal slices of luminance 0..=1, darkest first;
+    // brighter (or negative) pixels count toward the last (or first) bin
+    pub fn luminance_histogram(&self, bins: usize) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        if bins == 0 {
+            return counts;
+        }
+        for c in &self.pixels {
+            let bin = (c.luminance().clamp(0.0, 1.0) * bins as Float) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        counts
+    }
+
+    // scale every pixel so the average luminance becomes `target`;
+    // an all-black canvas is returned unchanged
+    pub fn auto_expose(&self, target: Float) -> Canvas {
+        let average = self.average_luminance();
+        if average <= 0.0 {
+            return self.clone();
+        }
+        let scale = target / average;
+        Canvas::from_pixels(
+            self.width,
+            self.height,
+            self.pixels.iter().map(|&c| c * scale).collect(),
+        )
+    }
+
+    // parse a plain (P3) PPM; `#` comments are skipped wherever they appear
+    pub fn from_ppm(ppm: &str) -> Result<Canvas, String> {
+        let mut tokens = ppm
+            .lines()
+            .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace());
+
+        match tokens.next() {
+            Some(Self::PPM_IDENTIFIER) => {}
+            other => return Err(format!("expected {}, got {other:?}", Self::PPM_IDENTIFIER)),
+        }
+
+        let mut number = |what: &str| -> Result<usize, String> {
+            let token = tokens.next().ok_or(format!("missing {what}"))?;
+            token
+                .parse()
+                .map_err(|_| format!("{what} should be a number, got {token:?}"))
+        };
+        let width = number("width")?;
+        let height = number("height")?;
+        let max = match number("max color value")? {
+            0 => return Err("max color value must be positive".to_string()),
+            max => max,
+        };
+        let count = width
+            .checked_mul(height)
+            .ok_or(format!("{width}x{height} is too many pixels"))?;
+
+        // grown as pixels are read, so huge dimensions in a short file can't
+        // reserve memory up front
+        let mut pixels = Vec::new();
+        let mut sample = |what: &str| -> Result<Float, String> {
+            match number(what)? {
+                v if v > max => Err(format!("{what} {v} exceeds the max color value {max}")),
+                v => Ok(v as Float / max as Float),
+            }
+        };
+        for _ in 0..count {
+            let red = sample("red")?;
+            let green = sample("green")?;
+            let blue = sample("blue")?;
+            pixels.push(Color::new(red, green, blue));
+        }
+
+        Ok(Canvas::from_pixels(width, height, pixels))
+    }
+
     pub fn to_ppm(&self) -> String {
+        self.to_ppm_with_line_len(Self::PPM_MAX_LINE_LEN as usize)
+    }
+
+    // like `to_ppm`, but wrapping pixel data at `max_len` characters per line;
+    // pass usize::MAX to write each row on a single line
+    pub fn to_ppm_with_line_len(&self, max_len: usize) -> String {
         let mut ppm = format!(
             "{}\n{} {}\n{}\n",
             Self::PPM_IDENTIFIER,
@@ -60,27 +324,22 @@ impl Canvas {
                 color_strings.push(Self::scale_to_ppm_data(color.blue).to_string());
             }
 
-            // Step 2: Write lines without exceeding PPM_MAX_LINE_LEN
+            // Step 2: Write lines without exceeding max_len
             let mut line = String::new();
-            let mut line_len = 0;
 
             for color in color_strings.into_iter() {
-                let color_len = color.len();
-                let sep = if line_len == 0 { "" } else { " " };
-                let addition_len = sep.len() + color_len;
+                let sep_len = if line.is_empty() { 0 } else { 1 };
 
-                if line_len + addition_len > Self::PPM_MAX_LINE_LEN as usize {
+                if !line.is_empty() && line.len() + sep_len + color.len() > max_len {
                     ppm.push_str(&line);
                     ppm.push('\n');
                     line.clear();
-                    line_len = 0;
                 }
 
                 if !line.is_empty() {
                     line.push(' ');
                 }
                 line.push_str(&color);
-                line_len += addition_len;
             }
 
             // Push any remaining line content
@@ -92,16 +351,83 @@ impl Canvas {
         ppm
     }
 
-    pub fn scale_to_ppm_data(color_scale: f64) -> u8 {
-        let max_color_val = f64::from(Self::PPM_MAX_COLOR_VALUE);
+    // like `to_ppm`, with the resolution and any settings in `metadata`
+    // written as comment lines after the identifier
+    pub fn to_ppm_with_metadata(&self, metadata: &PpmMetadata) -> String {
+        let mut comments = format!("# resolution: {}x{}\n", self.width, self.height);
+        if let Some(samples) = metadata.samples {
+            comments += &format!("# samples: {samples}\n");
+        }
+        if let Some(seed) = metadata.seed {
+            comments += &format!("# seed: {seed}\n");
+        }
+        if let Some(checksum) = metadata.scene_checksum {
+            comments += &format!("# scene checksum: {checksum:016x}\n");
+        }
+
+        let ppm = self.to_ppm();
+        let (identifier, rest) = ppm.split_once('\n').unwrap();
+        format!("{identifier}\n{comments}{rest}")
+    }
+
+    pub fn scale_to_ppm_data(color_scale: Float) -> u8 {
+        let max_color_val = Float::from(Self::PPM_MAX_COLOR_VALUE);
         let scaled_data = color_scale * max_color_val;
 
         scaled_data.clamp(0.0, max_color_val).round() as u8
     }
 
+    // encode as a Radiance .hdr image: uncompressed RGBE scanlines, where the
+    // three 8-bit mantissas share one exponent, so values above 1.0 survive
+    pub fn to_hdr(&self) -> Vec<u8> {
+        let mut hdr = format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )
+        .into_bytes();
+
+        for color in &self.pixels {
+            hdr.extend(Self::to_rgbe(*color));
+        }
+        hdr
+    }
+
+    fn to_rgbe(color: Color) -> [u8; 4] {
+        let v = color.red.max(color.green).max(color.blue);
+        if v < 1e-32 {
+            return [0; 4];
+        }
+
+        // v = m * 2^e with m in [0.5, 1)
+        let e = v.log2().floor() as i32 + 1;
+        let scale = 256.0 / Float::powi(2.0, e);
+        let channel = |c: Float| (c.max(0.0) * scale).min(255.0) as u8;
+
+        [
+            channel(color.red),
+            channel(color.green),
+            channel(color.blue),
+            (e + 128) as u8,
+        ]
+    }
+
+    pub fn write_hdr(&self, filename: &str) {
+        let hdr = self.to_hdr();
+
+        fs::create_dir_all(std::path::Path::new(filename).parent().unwrap())
+            .expect("directory should be created successfully");
+        fs::write(filename, hdr).expect("file should be written successfully");
+    }
+
     pub fn write_ppm(&self, filename: &str) {
-        let ppm = self.to_ppm();
+        Self::write_ppm_data(filename, self.to_ppm());
+    }
+
+    pub fn write_ppm_with_metadata(&self, filename: &str, metadata: &PpmMetadata) {
+        Self::write_ppm_data(filename, self.to_ppm_with_metadata(metadata));
+    }
 
+    fn write_ppm_data(filename: &str, ppm: String) {
         fs::create_dir_all(std::path::Path::new(filename).parent().unwrap())
             .expect("directory should be created successfully");
         fs::write(filename, ppm).expect("file should be written successfully");
@@ -111,8 +437,19 @@ impl Canvas {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::ROUNDING_EPSILON;
     use approx::assert_abs_diff_eq;
 
+    #[test]
+    fn canvas_from_fn() {
+        let c = Canvas::from_fn(2, 2, |x, y| Color::new(x as Float, y as Float, 0.5));
+
+        assert_eq!(c.pixel_at(0, 0), Color::new(0.0, 0.0, 0.5));
+        assert_eq!(c.pixel_at(1, 0), Color::new(1.0, 0.0, 0.5));
+        assert_eq!(c.pixel_at(0, 1), Color::new(0.0, 1.0, 0.5));
+        assert_eq!(c.pixel_at(1, 1), Color::new(1.0, 1.0, 0.5));
+    }
+
     #[test]
     fn new() {
         let canvas = Canvas::empty(10, 20);
@@ -135,6 +472,74 @@ mod tests {
         assert_eq!(canvas.pixel_at(2, 3), red);
     }
 
+    #[test]
+    fn clear_fills_every_pixel() {
+        let mut canvas = Canvas::empty(4, 3);
+        canvas.clear(Color::BLUE);
+
+        assert!(canvas.pixels.iter().all(|&p| p == Color::BLUE));
+    }
+
+    #[test]
+    fn draw_horizontal_line() {
+        let mut canvas = Canvas::empty(5, 3);
+        canvas.draw_line(1, 1, 3, 1, Color::WHITE);
+
+        let lit: Vec<_> = (0..5)
+            .map(|x| canvas.pixel_at(x, 1) == Color::WHITE)
+            .collect();
+        assert_eq!(lit, vec![false, true, true, true, false]);
+        assert!((0..5).all(|x| canvas.pixel_at(x, 0) == Color::BLACK));
+        assert!((0..5).all(|x| canvas.pixel_at(x, 2) == Color::BLACK));
+    }
+
+    #[test]
+    fn draw_vertical_line() {
+        let mut canvas = Canvas::empty(3, 5);
+        canvas.draw_line(2, 4, 2, 0, Color::WHITE);
+
+        assert!((0..5).all(|y| canvas.pixel_at(2, y) == Color::WHITE));
+        assert!((0..5).all(|y| canvas.pixel_at(1, y) == Color::BLACK));
+    }
+
+    #[test]
+    fn draw_diagonal_line() {
+        let mut canvas = Canvas::empty(4, 4);
+        canvas.draw_line(0, 0, 3, 3, Color::WHITE);
+
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if x == y { Color::WHITE } else { Color::BLACK };
+                assert_eq!(canvas.pixel_at(x, y), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn draw_line_clips_off_canvas_endpoints() {
+        let mut canvas = Canvas::empty(4, 3);
+        canvas.draw_line(-3, 1, 10, 1, Color::WHITE);
+
+        assert!((0..4).all(|x| canvas.pixel_at(x, 1) == Color::WHITE));
+        assert!((0..4).all(|x| canvas.pixel_at(x, 0) == Color::BLACK));
+    }
+
+    #[test]
+    fn draw_line_with_far_off_canvas_endpoints() {
+        let mut canvas = Canvas::empty(4, 3);
+        canvas.draw_line(-1_000_000_000, 0, 1_000_000_000, 0, Color::WHITE);
+
+        assert!((0..4).all(|x| canvas.pixel_at(x, 0) == Color::WHITE));
+        assert!((0..4).all(|x| canvas.pixel_at(x, 1) == Color::BLACK));
+
+        // extreme endpoints must not overflow, and fully hidden lines draw nothing
+        canvas.draw_line(isize::MIN, 2, isize::MAX, 2, Color::RED);
+        assert!((0..4).all(|x| canvas.pixel_at(x, 2) == Color::RED));
+        canvas.draw_line(-10, -5, 10, -5, Color::BLUE);
+        canvas.draw_line(isize::MIN, isize::MIN, isize::MIN, isize::MAX, Color::BLUE);
+        assert!(canvas.pixels.iter().all(|&p| p != Color::BLUE));
+    }
+
     #[test]
     fn ppm_header() {
         let canvas = Canvas::empty(5, 3);
@@ -147,6 +552,68 @@ mod tests {
         assert_eq!(lines.next().unwrap(), "255");
     }
 
+    #[test]
+    fn ppm_metadata_is_written_as_comments_and_skipped_on_read() {
+        let mut canvas = Canvas::empty(2, 2);
+        canvas.write_pixel(1, 0, Color::new(1.0, 0.2, 0.0));
+        canvas.write_pixel(0, 1, Color::WHITE);
+        let metadata = PpmMetadata {
+            samples: Some(16),
+            seed: Some(42),
+            scene_checksum: Some(0xbeef),
+        };
+
+        let ppm = canvas.to_ppm_with_metadata(&metadata);
+        let lines: Vec<&str> = ppm.lines().take(7).collect();
+        assert_eq!(
+            lines,
+            [
+                "P3",
+                "# resolution: 2x2",
+                "# samples: 16",
+                "# seed: 42",
+                "# scene checksum: 000000000000beef",
+                "2 2",
+                "255",
+            ]
+        );
+
+        let read = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(read, Canvas::from_ppm(&canvas.to_ppm()).unwrap());
+        assert_eq!(read.width, 2);
+        assert_eq!(read.pixel_at(0, 1), Color::WHITE);
+        assert_abs_diff_eq!(read.pixel_at(1, 0), Color::new(1.0, 51.0 / 255.0, 0.0));
+    }
+
+    #[test]
+    fn from_ppm_rejects_other_formats() {
+        assert!(Canvas::from_ppm("P6\n1 1\n255\n").is_err());
+        assert!(Canvas::from_ppm("P3\n2 1\n255\n0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn from_ppm_rejects_overflowing_dimensions() {
+        let ppm = format!("P3\n{} 2\n255\n", usize::MAX);
+
+        assert!(Canvas::from_ppm(&ppm).is_err());
+    }
+
+    #[test]
+    fn from_ppm_rejects_samples_above_the_max_color_value() {
+        assert_eq!(
+            Canvas::from_ppm("P3\n1 1\n255\n999 0 0\n"),
+            Err("red 999 exceeds the max color value 255".to_string())
+        );
+    }
+
+    #[test]
+    fn from_ppm_rejects_a_zero_max_color_value() {
+        assert_eq!(
+            Canvas::from_ppm("P3\n1 1\n0\n0 0 0\n"),
+            Err("max color value must be positive".to_string())
+        );
+    }
+
     #[test]
     fn ppm_pixel_data() {
         let mut canvas = Canvas::empty(5, 3);
@@ -206,6 +673,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ppm_with_custom_line_len() {
+        let mut canvas = Canvas::empty(10, 2);
+        canvas.clear(Color::new(1.0, 0.8, 0.6));
+        let data_lines = |ppm: String| ppm.lines().skip(3).map(String::from).collect::<Vec<_>>();
+
+        let short = data_lines(canvas.to_ppm_with_line_len(20));
+        assert!(short.len() > data_lines(canvas.to_ppm()).len());
+        assert!(short.iter().all(|l| l.len() <= 20));
+        assert_eq!(short[0], "255 204 153 255 204");
+
+        let long = data_lines(canvas.to_ppm_with_line_len(usize::MAX));
+        assert_eq!(long.len(), canvas.height);
+        assert_eq!(long[0], ["255 204 153"; 10].join(" "));
+    }
+
     #[test]
     fn ppm_terminate_with_newline() {
         let canvas = Canvas::empty(5, 3);
@@ -214,4 +697,104 @@ mod tests {
 
         assert!(ppm.ends_with('\n'));
     }
+
+    #[test]
+    fn split_channels_of_a_red_canvas() {
+        let mut canvas = Canvas::empty(3, 2);
+        canvas.clear(Color::RED);
+
+        let (red, green, blue) = canvas.split_channels();
+        assert!(red.pixels.iter().all(|&c| c == Color::WHITE));
+        assert!(green.pixels.iter().all(|&c| c == Color::BLACK));
+        assert!(blue.pixels.iter().all(|&c| c == Color::BLACK));
+        assert_eq!((red.width, red.height), (3, 2));
+    }
+
+    #[test]
+    fn auto_expose_scales_to_the_target_luminance() {
+        let mut canvas = Canvas::empty(4, 3);
+        canvas.clear(Color::new(0.25, 0.25, 0.25));
+        assert_abs_diff_eq!(canvas.average_luminance(), 0.25);
+
+        let exposed = canvas.auto_expose(0.5);
+        assert_abs_diff_eq!(exposed.average_luminance(), 0.5);
+        for (&after, &before) in exposed.pixels.iter().zip(&canvas.pixels) {
+            assert_abs_diff_eq!(after, before * 2.0);
+        }
+    }
+
+    #[test]
+    fn luminance_histogram_of_half_black_half_white() {
+        let canvas = Canvas::from_fn(4, 4, |x, _| if x < 2 { Color::BLACK } else { Color::WHITE });
+        let histogram = canvas.luminance_histogram(8);
+
+        assert_eq!(histogram.len(), 8);
+        assert_eq!(histogram[0], 8);
+        assert_eq!(histogram[7], 8);
+        assert_eq!(histogram.iter().sum::<usize>(), 16);
+
+        // over-bright pixels land in the last bin
+        let bright = Canvas::from_fn(2, 1, |_, _| Color::new(3.0, 3.0, 3.0));
+        assert_eq!(bright.luminance_histogram(4), vec![0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn auto_expose_leaves_a_black_canvas_alone() {
+        let canvas = Canvas::empty(2, 2);
+        assert_eq!(canvas.auto_expose(0.5), canvas);
+    }
+
+    #[test]
+    fn vignette_darkens_corners_by_strength() {
+        let mut canvas = Canvas::empty(5, 5);
+        canvas.clear(Color::new(0.8, 0.6, 0.4));
+
+        let weak = canvas.vignette(0.25);
+        let strong = canvas.vignette(0.5);
+
+        assert_eq!(weak.pixel_at(2, 2), canvas.pixel_at(2, 2));
+        assert_eq!(strong.pixel_at(2, 2), canvas.pixel_at(2, 2));
+
+        // the corner pixel's center is 0.8 of the half-diagonal from the center
+        let darkening = |v: &Canvas| 1.0 - v.pixel_at(0, 0).red / canvas.pixel_at(0, 0).red;
+        assert_abs_diff_eq!(darkening(&weak), 0.25 * 0.64, epsilon = ROUNDING_EPSILON);
+        assert_abs_diff_eq!(
+            darkening(&strong),
+            2.0 * darkening(&weak),
+            epsilon = ROUNDING_EPSILON
+        );
+    }
+
+    #[test]
+    fn hdr_round_trips_values_above_one() {
+        // minimal reader for the flat RGBE scanlines written by `to_hdr`
+        fn decode(hdr: &[u8]) -> Vec<Color> {
+            let header_end = hdr.windows(2).position(|w| w == b"\n\n").unwrap() + 2;
+            let data_start =
+                header_end + hdr[header_end..].iter().position(|&b| b == b'\n').unwrap() + 1;
+            hdr[data_start..]
+                .chunks(4)
+                .map(|p| {
+                    let f = Float::powi(2.0, p[3] as i32 - 136);
+                    Color::new(p[0] as Float * f, p[1] as Float * f, p[2] as Float * f)
+                })
+                .collect()
+        }
+
+        let mut canvas = Canvas::empty(3, 1);
+        canvas.write_pixel(0, 0, Color::new(4.0, 1.0, 0.25));
+        canvas.write_pixel(1, 0, Color::new(0.3, 12.5, 0.0));
+
+        let hdr = canvas.to_hdr();
+        assert!(hdr.starts_with(b"#?RADIANCE\n"));
+
+        let decoded = decode(&hdr);
+        assert_eq!(decoded.len(), 3);
+        for (original, decoded) in canvas.pixels.iter().zip(decoded) {
+            // each channel is within one mantissa step of the largest channel
+            let step = original.red.max(original.green).max(original.blue) / 128.0;
+            assert_abs_diff_eq!(*original, decoded, epsilon = step.max(1e-12));
+        }
+        assert_eq!(decode(&hdr)[0], Color::new(4.0, 1.0, 0.25));
+    }
 }
@@ -1,16 +1,18 @@
-use std::ops;
+use std::{fmt, ops};
 
+use crate::utils::EPSILON;
+use crate::utils::Float;
 use approx::AbsDiffEq;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
-    pub red: f64,
-    pub green: f64,
-    pub blue: f64,
+    pub red: Float,
+    pub green: Float,
+    pub blue: Float,
 }
 
 impl Color {
-    pub const fn new(red: f64, green: f64, blue: f64) -> Self {
+    pub const fn new(red: Float, green: Float, blue: Float) -> Self {
         Self { red, green, blue }
     }
 
@@ -102,6 +104,11 @@ impl Color {
 }
 
 impl Color {
+    // compare against `other` using a caller-chosen tolerance
+    pub fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        self.abs_diff_eq(other, epsilon)
+    }
+
     pub fn clamp01(self) -> Self {
         Self::new(
             self.red.clamp(0.0, 1.0),
@@ -109,6 +116,93 @@ impl Color {
             self.blue.clamp(0.0, 1.0),
         )
     }
+
+    // bring an over-bright color into [0, 1] by blending it toward the gray of
+    // equal luminance until its brightest channel is 1, rather than clipping
+    // each channel and shifting the hue; too bright to fit at all becomes white.
+    // Negative channels are still clamped to 0.
+    pub fn clamp_desaturate(self) -> Self {
+        let max = self.red.max(self.green).max(self.blue);
+        if max <= 1.0 {
+            return self.clamp01();
+        }
+
+        let l = self.luminance();
+        if l >= 1.0 {
+            return Color::WHITE;
+        }
+
+        let gray = Color::new(l, l, l);
+        Color::lerp(gray, self, (1.0 - l) / (max - l)).clamp01()
+    }
+
+    // linear interpolation: `a` at t = 0, `b` at t = 1
+    pub fn lerp(a: Color, b: Color, t: Float) -> Color {
+        a + (b - a) * t
+    }
+
+    // weighted average of `colors`; weights need not sum to 1
+    pub fn mix_many(colors: &[Color], weights: &[Float]) -> Color {
+        debug_assert_eq!(colors.len(), weights.len());
+        let total: Float = weights.iter().sum();
+        if total == 0.0 {
+            return Color::BLACK;
+        }
+
+        colors
+            .iter()
+            .zip(weights)
+            .fold(Color::BLACK, |acc, (&c, &w)| acc + c * w)
+            * (1.0 / total)
+    }
+
+    // relative luminance of a linear RGB color, using the Rec. 709 weights
+    pub fn luminance(self) -> Float {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
+    // Convert a linear RGB color to Oklab, returned as (L, a, b).
+    // See https://bottosson.github.io/posts/oklab/ for the reference matrices.
+    #[allow(clippy::excessive_precision)] // the reference digits, rounded under `f32`
+    pub fn to_oklab(self) -> (Float, Float, Float) {
+        let l = 0.4122214708 * self.red + 0.5363325363 * self.green + 0.0514459929 * self.blue;
+        let m = 0.2119034982 * self.red + 0.6806995451 * self.green + 0.1073969566 * self.blue;
+        let s = 0.0883024619 * self.red + 0.2817188376 * self.green + 0.6299787005 * self.blue;
+
+        let l_ = l.cbrt();
+        let m_ = m.cbrt();
+        let s_ = s.cbrt();
+
+        (
+            0.2104542553 * l_ + 0.7936177850 * m_ - 0.0040720468 * s_,
+            1.9779984951 * l_ - 2.4285922050 * m_ + 0.4505937099 * s_,
+            0.0259040371 * l_ + 0.7827717662 * m_ - 0.8086757660 * s_,
+        )
+    }
+
+    // Convert an Oklab (L, a, b) triple back to a linear RGB color.
+    #[allow(clippy::excessive_precision)] // the reference digits, rounded under `f32`
+    pub fn from_oklab(l: Float, a: Float, b: Float) -> Self {
+        let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
+        let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
+        let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
+
+        let l = l_.powi(3);
+        let m = m_.powi(3);
+        let s = s_.powi(3);
+
+        Self::new(
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        )
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rgb({}, {}, {})", self.red, self.green, self.blue)
+    }
 }
 
 impl ops::Add for Color {
@@ -135,10 +229,10 @@ impl ops::Sub for Color {
     }
 }
 
-impl ops::Mul<f64> for Color {
+impl ops::Mul<Float> for Color {
     type Output = Self;
 
-    fn mul(self, other: f64) -> Self::Output {
+    fn mul(self, other: Float) -> Self::Output {
         Self::new(self.red * other, self.green * other, self.blue * other)
     }
 }
@@ -156,10 +250,10 @@ impl ops::Mul for Color {
 }
 
 impl AbsDiffEq for Color {
-    type Epsilon = f64;
+    type Epsilon = Float;
 
     fn default_epsilon() -> Self::Epsilon {
-        1e-4
+        EPSILON
     }
 
     fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
@@ -182,6 +276,33 @@ mod tests {
         assert_abs_diff_eq!(c.blue, 1.7);
     }
 
+    #[test]
+    fn luminance_weights_green_most() {
+        assert_abs_diff_eq!(Color::WHITE.luminance(), 1.0);
+        assert_abs_diff_eq!(Color::BLACK.luminance(), 0.0);
+        assert!(Color::GREEN.luminance() > Color::RED.luminance());
+        assert!(Color::RED.luminance() > Color::BLUE.luminance());
+    }
+
+    #[test]
+    fn clamp_desaturate_keeps_the_whiteness_of_over_bright_colors() {
+        let c = Color::new(1.5, 0.2, 0.2);
+        let clamped = c.clamp_desaturate();
+
+        // pink, not the pure red that clamp01 gives
+        assert_abs_diff_eq!(clamped.red, 1.0);
+        assert_abs_diff_eq!(clamped.green, clamped.blue);
+        assert!(clamped.green > 0.3);
+        assert_abs_diff_eq!(clamped.luminance(), c.luminance(), epsilon = 1e-12);
+        assert_eq!(c.clamp01(), Color::new(1.0, 0.2, 0.2));
+
+        assert_eq!(
+            Color::new(0.3, 0.6, 0.9).clamp_desaturate(),
+            Color::new(0.3, 0.6, 0.9)
+        );
+        assert_eq!(Color::new(3.0, 2.0, 2.0).clamp_desaturate(), Color::WHITE);
+    }
+
     #[test]
     fn add_color_to_color() {
         let c1 = Color::new(0.9, 0.6, 0.75);
@@ -210,4 +331,53 @@ mod tests {
 
         assert_abs_diff_eq!(c1 * c2, Color::new(0.9, 0.2, 0.04));
     }
+
+    #[test]
+    fn approx_eq_with_custom_epsilon() {
+        let a = Color::new(0.5, 0.25, 0.75);
+        let b = Color::new(0.501, 0.25, 0.749);
+
+        assert!(a.approx_eq(&b, 1e-2));
+        assert!(!a.approx_eq(&b, 1e-6));
+    }
+
+    #[test]
+    fn oklab_round_trip() {
+        let c = Color::new(0.2, 0.7, 0.4);
+        let (l, a, b) = c.to_oklab();
+
+        assert_abs_diff_eq!(Color::from_oklab(l, a, b), c);
+    }
+
+    #[test]
+    fn oklab_of_white_is_achromatic() {
+        let (l, a, b) = Color::WHITE.to_oklab();
+
+        assert_abs_diff_eq!(l, 1.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(a, 0.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(b, 0.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn lerp_halfway_from_black_to_white_is_mid_gray() {
+        assert_eq!(Color::lerp(Color::BLACK, Color::WHITE, 0.5), Color::GRAY);
+    }
+
+    #[test]
+    fn weighted_mix_leans_toward_the_heavier_color() {
+        let c = Color::mix_many(&[Color::RED, Color::BLUE], &[0.25, 0.75]);
+
+        assert_abs_diff_eq!(c, Color::new(0.25, 0.0, 0.75));
+        assert!(c.blue > c.red);
+        assert_eq!(Color::mix_many(&[Color::RED], &[0.0]), Color::BLACK);
+    }
+
+    #[test]
+    fn display_color_as_rgb() {
+        assert_eq!(Color::RED.to_string(), "rgb(1, 0, 0)");
+        assert_eq!(
+            Color::new(0.5, 0.25, 1.5).to_string(),
+            "rgb(0.5, 0.25, 1.5)"
+        );
+    }
 }
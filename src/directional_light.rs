@@ -0,0 +1,104 @@
+use crate::color::Color;
+use crate::light::{Light, LightSource};
+use crate::point::Point;
+use crate::utils::Float;
+use crate::vector::Vector;
+use crate::world::World;
+
+// A light infinitely far away, like the sun: every ray from it travels along
+// the same `direction`, so shading doesn't depend on where a surface is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    pub direction: Vector, // unit vector the light travels along
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector, intensity: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+}
+
+impl LightSource for DirectionalLight {
+    fn intensity_at(&self, point: Point, world: &World) -> Float {
+        world.intensity_at(&Light::Directional(*self), point)
+    }
+
+    fn direction_to(&self, _point: Point) -> Vector {
+        -self.direction
+    }
+
+    fn color(&self) -> Color {
+        self.intensity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Transformation;
+    use crate::point_light::PointLight;
+    use crate::ray::Ray;
+    use crate::shapes::Shape;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn direction_is_normalized() {
+        let light = DirectionalLight::new(Vector::new(0.0, -2.0, 0.0), Color::WHITE);
+
+        assert_eq!(light.direction, Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(
+            light.direction_to(Point::new(3.0, 4.0, 5.0)),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn coplanar_surfaces_are_shaded_alike_wherever_they_are() {
+        // two floor tiles in the same plane, far apart
+        let tile =
+            |x: Float| Shape::quad().with_transform(Transformation::translation(x, 0.0, 0.0));
+        let looking_down =
+            |x: Float| Ray::new(Point::new(x, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+
+        let mut sunlit = World::empty();
+        sunlit.objects = vec![tile(0.0), tile(100.0)];
+        sunlit.add_light(DirectionalLight::new(
+            Vector::new(1.0, -1.0, 0.5),
+            Color::WHITE,
+        ));
+        assert_abs_diff_eq!(
+            sunlit.color_at(looking_down(0.0), 5),
+            sunlit.color_at(looking_down(100.0), 5)
+        );
+
+        // a point light, in contrast, falls on them at different angles
+        let mut lamp = World::empty();
+        lamp.objects = sunlit.objects.clone();
+        lamp.add_light(PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE));
+        assert_ne!(
+            lamp.color_at(looking_down(0.0), 5),
+            lamp.color_at(looking_down(100.0), 5)
+        );
+    }
+
+    #[test]
+    fn shadows_are_cast_along_the_light_direction() {
+        let mut world = World::empty();
+        world.add_light(DirectionalLight::new(
+            Vector::new(0.0, -1.0, 0.0),
+            Color::WHITE,
+        ));
+        // a blocker far above one point, and nothing above the other
+        world
+            .objects
+            .push(Shape::sphere().with_transform(Transformation::translation(0.0, 1000.0, 0.0)));
+        let light = world.lights[0].clone();
+
+        assert_eq!(world.intensity_at(&light, Point::ORIGIN), 0.0);
+        assert_eq!(world.intensity_at(&light, Point::new(5.0, 0.0, 0.0)), 1.0);
+    }
+}
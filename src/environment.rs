@@ -0,0 +1,115 @@
+use crate::utils::consts::PI;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::pattern::sample_bilinear_wrapped;
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// What rays that escape the scene see
+#[derive(Debug, Clone, PartialEq)]
+pub enum Environment {
+    Solid(Color),
+    // a latitude/longitude image wrapped around the scene: u follows the
+    // azimuth around y, v runs from straight down (0) to straight up (1)
+    Equirect(Canvas),
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::Solid(Color::BLACK)
+    }
+}
+
+impl Environment {
+    pub fn color_for(&self, direction: Vector) -> Color {
+        match self {
+            Environment::Solid(color) => *color,
+            Environment::Equirect(image) => {
+                let (u, v) = equirect_map(direction);
+                sample_bilinear_wrapped(image, u, v)
+            }
+        }
+    }
+}
+
+// like the sphere's spherical_map, but for a direction rather than a point
+pub(crate) fn equirect_map(direction: Vector) -> (Float, Float) {
+    let d = direction.normalize();
+    let theta = d.x.atan2(d.z);
+    let u = 1.0 - (theta / (2.0 * PI) + 0.5);
+    let v = 0.5 + d.y.clamp(-1.0, 1.0).asin() / PI;
+    (u, v)
+}
+
+// the unit direction that `equirect_map` sends to (u, v)
+pub(crate) fn equirect_direction(u: Float, v: Float) -> Vector {
+    let theta = (0.5 - u) * 2.0 * PI;
+    let elevation = (v - 0.5) * PI;
+    Vector::new(
+        elevation.cos() * theta.sin(),
+        elevation.sin(),
+        elevation.cos() * theta.cos(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ROUNDING_EPSILON;
+    use approx::assert_abs_diff_eq;
+
+    // a sky that is white along the top row, blue along the bottom row
+    // and gray in between
+    fn sky() -> Canvas {
+        Canvas::from_fn(8, 4, |_, y| match y {
+            0 => Color::WHITE,
+            3 => Color::BLUE,
+            _ => Color::GRAY,
+        })
+    }
+
+    #[test]
+    fn equirect_map_matches_the_spherical_map_convention() {
+        assert_eq!(equirect_map(Vector::new(0.0, 0.0, -1.0)), (0.0, 0.5));
+        assert_eq!(equirect_map(Vector::new(1.0, 0.0, 0.0)), (0.25, 0.5));
+        assert_eq!(equirect_map(Vector::new(0.0, 0.0, 1.0)), (0.5, 0.5));
+        assert_abs_diff_eq!(equirect_map(Vector::new(0.0, 2.0, 0.0)).1, 1.0);
+        assert_abs_diff_eq!(equirect_map(Vector::new(0.0, -1.0, 0.0)).1, 0.0);
+    }
+
+    #[test]
+    fn equirect_direction_inverts_the_map() {
+        for d in [
+            Vector::new(0.3, 0.5, -0.8),
+            Vector::new(-1.0, 0.2, 0.1),
+            Vector::new(0.0, -0.4, 1.0),
+        ] {
+            let (u, v) = equirect_map(d);
+            assert_abs_diff_eq!(
+                equirect_direction(u, v),
+                d.normalize(),
+                epsilon = ROUNDING_EPSILON
+            );
+        }
+    }
+
+    #[test]
+    fn straight_up_samples_the_top_row() {
+        let env = Environment::Equirect(sky());
+
+        assert_eq!(env.color_for(Vector::new(0.0, 1.0, 0.0)), Color::WHITE);
+        assert_eq!(env.color_for(Vector::new(0.0, -1.0, 0.0)), Color::BLUE);
+    }
+
+    #[test]
+    fn solid_environment_is_the_same_everywhere() {
+        let env = Environment::Solid(Color::RED);
+
+        assert_eq!(env.color_for(Vector::new(0.3, -0.2, 1.0)), Color::RED);
+        assert_eq!(
+            Environment::default().color_for(Vector::new(0.0, 1.0, 0.0)),
+            Color::BLACK
+        );
+    }
+}
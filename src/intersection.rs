@@ -1,22 +1,24 @@
 use approx::AbsDiffEq;
 use std::ptr;
 
+use crate::optics::fresnel_schlick;
 use crate::point::Point;
 use crate::ray::Ray;
 use crate::shapes::Shape;
 use crate::utils::EPSILON;
+use crate::utils::Float;
 use crate::vector::Vector;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LocalHits {
-    None,          // 0 intersections
-    One(f64),      // 1 intersection at t
-    Two(f64, f64), // 2 intersections at t1 and t2 (sorted)
+    None,              // 0 intersections
+    One(Float),        // 1 intersection at t
+    Two(Float, Float), // 2 intersections at t1 and t2 (sorted)
 }
 
 impl LocalHits {
-    pub fn iter(self) -> impl Iterator<Item = f64> {
-        let arr: [Option<f64>; 2] = match self {
+    pub fn iter(self) -> impl Iterator<Item = Float> {
+        let arr: [Option<Float>; 2] = match self {
             LocalHits::None => [None, None],
             LocalHits::One(a) => [Some(a), None],
             LocalHits::Two(a, b) => [Some(a), Some(b)],
@@ -28,8 +30,10 @@ impl LocalHits {
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Intersection<'a> {
-    pub t: f64,
+    pub t: Float,
     pub s: &'a Shape,
+    // which of a mesh's triangles was hit, so its normal needn't be searched for
+    pub triangle: Option<usize>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -38,17 +42,23 @@ pub struct Computations<'a> {
     pub point: Point,
     pub eye_vector: Vector,
     pub normal_vector: Vector,
+    // face normal on the eye's side, used for the over/under points
+    pub geometric_normal: Vector,
     pub inside: bool,
     pub over_point: Point,
     pub reflect_vector: Vector,
-    pub n1: f64,
-    pub n2: f64,
+    pub n1: Float,
+    pub n2: Float,
     pub under_point: Point,
 }
 
 impl<'a> Intersection<'a> {
-    pub fn new(t: f64, s: &'a Shape) -> Self {
-        Self { t, s }
+    pub fn new(t: Float, s: &'a Shape) -> Self {
+        Self {
+            t,
+            s,
+            triangle: None,
+        }
     }
 
     // pre-calculate the values that will be used to compute the shading
@@ -56,14 +66,24 @@ impl<'a> Intersection<'a> {
         &'a self,
         ray: Ray,
         intersections: &Intersections<'a>,
+    ) -> Computations<'a> {
+        self.prepare_computations_among(ray, intersections.list.iter().cloned())
+    }
+
+    // as `prepare_computations`, taking the ray's sorted intersections as an
+    // iterator, so callers needn't collect them into an `Intersections` first
+    pub(crate) fn prepare_computations_among(
+        &self,
+        ray: Ray,
+        intersections: impl IntoIterator<Item = Intersection<'a>>,
     ) -> Computations<'a> {
         let mut containers: Vec<&Shape> = Vec::new();
 
         let mut n1 = None;
         let mut n2 = None;
 
-        for i in &intersections.list {
-            if i == self {
+        for i in intersections {
+            if i == *self {
                 n1 = containers
                     .last()
                     .map(|shape| shape.material().refractive_index);
@@ -76,7 +96,7 @@ impl<'a> Intersection<'a> {
                 None => containers.push(i.s),
             }
 
-            if i == self {
+            if i == *self {
                 n2 = containers
                     .last()
                     .map(|shape| shape.material().refractive_index);
@@ -86,7 +106,7 @@ impl<'a> Intersection<'a> {
 
         let point = ray.position(self.t);
         let eye_vector = -ray.direction;
-        let normal_vector = self.s.normal_at(point);
+        let normal_vector = self.s.normal_at_triangle(point, self.triangle);
         let inside = normal_vector.dot(eye_vector) < 0.0;
 
         let normal_vector = if inside {
@@ -95,18 +115,28 @@ impl<'a> Intersection<'a> {
             normal_vector
         };
 
+        // smooth shading normals can lean past the true surface at grazing angles,
+        // so offsets use the face normal, turned toward the eye
+        let geometric_normal = self.s.geometric_normal_at_triangle(point, self.triangle);
+        let geometric_normal = if geometric_normal.dot(eye_vector) < 0.0 {
+            -geometric_normal
+        } else {
+            geometric_normal
+        };
+
         let reflect_vector = ray.direction.reflect(normal_vector);
         // due to floating point math errors, we need to offset the point slightly
         // as it can sometimes calculate the point to be just below the surface of the sphere
         // instead we nudge it slightly in the normal direction so it's outside of the sphere
-        let over_point = point + normal_vector * EPSILON;
-        let under_point = point - normal_vector * EPSILON;
+        let over_point = point + geometric_normal * self.s.epsilon();
+        let under_point = point - geometric_normal * self.s.epsilon();
 
         Computations {
             object: self.s,
             point,
             eye_vector,
             normal_vector,
+            geometric_normal,
             inside,
             over_point,
             reflect_vector,
@@ -119,35 +149,17 @@ impl<'a> Intersection<'a> {
 
 impl<'a> Computations<'a> {
     // Schlick's approximation for reflectance
-    pub fn schlick(&self) -> f64 {
+    pub fn schlick(&self) -> Float {
         // find the cosine of the angle between the eye and normal vectors
-        let mut cos = self.eye_vector.dot(self.normal_vector);
-
-        // total internal reflection can only occur if n1 > n2
-        if self.n1 > self.n2 {
-            let n_ratio = self.n1 / self.n2;
-            let sin2_t = n_ratio.powi(2) * (1.0 - cos.powi(2));
-            if sin2_t > 1.0 {
-                return 1.0;
-            }
-
-            // compute cosine of theta_t using trig identity
-            let cos_t = (1.0 - sin2_t).sqrt();
-
-            // when n1 > n2, use cos(theta_t) instead
-            cos = cos_t;
-        }
-
-        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
-        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+        fresnel_schlick(self.eye_vector.dot(self.normal_vector), self.n1, self.n2)
     }
 }
 
 impl<'a> AbsDiffEq for Intersection<'a> {
-    type Epsilon = f64;
+    type Epsilon = Float;
 
     fn default_epsilon() -> Self::Epsilon {
-        1e-5
+        EPSILON
     }
 
     fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
@@ -163,8 +175,10 @@ pub struct Intersections<'a> {
 // intersections are always sorted so it's easy to find the closest intersection
 impl<'a> Intersections<'a> {
     pub fn new(mut intersections: Vec<Intersection<'a>>) -> Self {
-        intersections
-            .sort_unstable_by(|a, b| a.t.partial_cmp(&b.t).unwrap_or(std::cmp::Ordering::Equal));
+        // a stable sort, so equal-t hits keep the order they were passed in;
+        // gathered object by object, that is object index order, matching
+        // `World::intersections`
+        intersections.sort_by(|a, b| a.t.total_cmp(&b.t));
         Intersections {
             list: intersections,
         }
@@ -184,13 +198,22 @@ impl<'a> Intersections<'a> {
         self.list.iter().find(|&intersection| intersection.t > 0.0)
     }
 
-    pub fn all(&self) -> &[Intersection] {
+    // the nth (from 0) intersection in front of the ray's origin; nth_positive(0)
+    // is the hit, larger n peel back the surfaces behind it
+    pub fn nth_positive(&self, n: usize) -> Option<&Intersection<'a>> {
+        self.list
+            .iter()
+            .filter(|intersection| intersection.t > 0.0)
+            .nth(n)
+    }
+
+    pub fn all(&self) -> &[Intersection<'a>] {
         &self.list
     }
 
     // Create from a list that is already sorted by `t` (ascending).
     pub fn from_sorted(list: Vec<Intersection<'a>>) -> Self {
-        debug_assert!(list.windows(2).all(|w| w[0].t <= w[1].t));
+        debug_assert!(list.windows(2).all(|w| w[0].t.total_cmp(&w[1].t).is_le()));
         Self { list }
     }
 
@@ -229,6 +252,28 @@ mod tests {
         assert_eq!(i.list[1].s, &s);
     }
 
+    #[test]
+    fn coincident_hits_keep_the_order_they_were_given_in() {
+        // the same sphere twice, so every hit is shared
+        let objects = [Shape::from(Sphere::new()), Shape::from(Sphere::new())];
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        for order in [[0, 1], [1, 0]] {
+            let list = order
+                .iter()
+                .flat_map(|&i| objects[i].intersect(r).into_vec())
+                .collect();
+            let xs = Intersections::new(list);
+
+            let ts: Vec<Float> = xs.all().iter().map(|i| i.t).collect();
+            assert_eq!(ts, vec![4.0, 4.0, 6.0, 6.0]);
+            for pair in xs.all().chunks(2) {
+                assert!(ptr::eq(pair[0].s, &objects[order[0]]));
+                assert!(ptr::eq(pair[1].s, &objects[order[1]]));
+            }
+        }
+    }
+
     #[test]
     fn hit_all_positive() {
         let s = Shape::from(Sphere::new());
@@ -321,15 +366,15 @@ mod tests {
     fn prepare_computations_reflection() {
         let r = Ray::new(
             Point::new(0.0, 1.0, -1.0),
-            Vector::new(0.0, -2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0),
+            Vector::new(0.0, -Float::sqrt(2.0) / 2.0, Float::sqrt(2.0) / 2.0),
         );
         let s = Shape::from(Plane::new());
-        let i = Intersection::new(2.0_f64.sqrt(), &s);
+        let i = Intersection::new(Float::sqrt(2.0), &s);
         let comps = i.prepare_computations(r, &Intersections::new(vec![i.clone()]));
 
         assert_eq!(
             comps.reflect_vector,
-            Vector::new(0.0, 2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0)
+            Vector::new(0.0, Float::sqrt(2.0) / 2.0, Float::sqrt(2.0) / 2.0)
         );
     }
 
@@ -394,16 +439,55 @@ mod tests {
         assert!(comps.point.z < comps.under_point.z);
     }
 
+    #[test]
+    fn over_and_under_points_use_the_shape_epsilon() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Shape::from(Sphere::new()).with_epsilon(0.01);
+        let i = Intersection::new(4.0, &s);
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i.clone()]));
+
+        assert_abs_diff_eq!(comps.over_point.z, -1.01);
+        assert_abs_diff_eq!(comps.under_point.z, -0.99);
+    }
+
+    #[test]
+    fn smooth_triangle_offsets_along_the_face_normal() {
+        use crate::shapes::Triangle;
+
+        // the face normal is (0, 0, -1), but the vertex normals lean hard toward -x
+        let n = Vector::new(-1.0, 0.0, -0.3).normalize();
+        let s = Shape::from(Triangle::smooth(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            n,
+            n,
+            n,
+        ));
+        // a grazing ray from the -z side, heading mostly along -x
+        let direction = Vector::new(-0.99, 0.0, 0.1).normalize();
+        let r = Ray::new(Point::new(0.0, 0.5, 0.0) - direction * 5.0, direction);
+        let xs = s.intersect(r);
+        let comps = xs.hit().unwrap().prepare_computations(r, &xs);
+
+        assert_abs_diff_eq!(comps.geometric_normal, Vector::new(0.0, 0.0, -1.0));
+        // the shading normal, turned toward the eye, points behind the plane...
+        assert!(comps.normal_vector.z > 0.0);
+        // ...but the offsets land on either side of the triangle's plane
+        assert!(comps.over_point.z < -EPSILON / 2.0);
+        assert!(comps.under_point.z > EPSILON / 2.0);
+    }
+
     #[test]
     fn schlick_approximation_under_total_internal_reflection() {
         let s = Shape::glass_sphere();
         let r = Ray::new(
-            Point::new(0.0, 0.0, 2.0_f64.sqrt() / 2.0),
+            Point::new(0.0, 0.0, Float::sqrt(2.0) / 2.0),
             Vector::new(0.0, 1.0, 0.0),
         );
         let xs = Intersections::new(vec![
-            Intersection::new(-2.0_f64.sqrt() / 2.0, &s),
-            Intersection::new(2.0_f64.sqrt() / 2.0, &s),
+            Intersection::new(-Float::sqrt(2.0) / 2.0, &s),
+            Intersection::new(Float::sqrt(2.0) / 2.0, &s),
         ]);
         let comps = xs.list[1].prepare_computations(r, &xs);
 
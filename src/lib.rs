@@ -1,14 +1,26 @@
+pub mod area_light;
+pub mod bounds;
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod directional_light;
+pub mod environment;
 pub mod intersection;
+pub mod light;
 pub mod material;
 pub mod matrix;
+pub mod noise;
+pub mod obj_file;
+pub mod optics;
 pub mod pattern;
 pub mod point;
 pub mod point_light;
 pub mod ray;
+pub mod sampling;
+pub mod shadow_map;
 pub mod shapes;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_fixtures;
 pub mod utils;
 pub mod vector;
 pub mod world;
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::area_light::AreaLight;
+use crate::color::Color;
+use crate::directional_light::DirectionalLight;
+use crate::point::Point;
+use crate::point_light::PointLight;
+use crate::utils::Float;
+use crate::vector::Vector;
+use crate::world::World;
+
+// What shading needs from a light. Implement it for your own light model and
+// add it to a world with `Light::custom`.
+pub trait LightSource: fmt::Debug + Send + Sync {
+    // fraction of the light that reaches `point`, from 0.0 (fully shadowed)
+    // to 1.0 (fully lit)
+    fn intensity_at(&self, point: Point, world: &World) -> Float;
+
+    // unit vector from `point` toward the light
+    fn direction_to(&self, point: Point) -> Vector;
+
+    fn color(&self) -> Color;
+}
+
+impl<L: LightSource + ?Sized> LightSource for &L {
+    fn intensity_at(&self, point: Point, world: &World) -> Float {
+        (**self).intensity_at(point, world)
+    }
+
+    fn direction_to(&self, point: Point) -> Vector {
+        (**self).direction_to(point)
+    }
+
+    fn color(&self) -> Color {
+        (**self).color()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Light {
+    Point(PointLight),
+    Area(AreaLight),
+    Directional(DirectionalLight),
+    Custom(Arc<dyn LightSource>),
+}
+
+impl Light {
+    pub fn custom(light: impl LightSource + 'static) -> Self {
+        Light::Custom(Arc::new(light))
+    }
+
+    // representative position of the light (the center for area lights);
+    // None for directional lights, which are infinitely far away, and for
+    // custom lights, which only describe themselves through `LightSource`
+    pub fn position(&self) -> Option<Point> {
+        match self {
+            Light::Point(l) => Some(l.position),
+            Light::Area(l) => Some(l.position),
+            Light::Directional(_) | Light::Custom(_) => None,
+        }
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.color()
+    }
+}
+
+impl LightSource for Light {
+    fn intensity_at(&self, point: Point, world: &World) -> Float {
+        world.intensity_at(self, point)
+    }
+
+    fn direction_to(&self, point: Point) -> Vector {
+        match self {
+            Light::Point(l) => l.direction_to(point),
+            Light::Area(l) => l.direction_to(point),
+            Light::Directional(l) => l.direction_to(point),
+            Light::Custom(l) => l.direction_to(point),
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Light::Point(l) => l.color(),
+            Light::Area(l) => l.color(),
+            Light::Directional(l) => l.color(),
+            Light::Custom(l) => l.color(),
+        }
+    }
+}
+
+// custom lights are equal only to themselves
+impl PartialEq for Light {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Light::Point(a), Light::Point(b)) => a == b,
+            (Light::Area(a), Light::Area(b)) => a == b,
+            (Light::Directional(a), Light::Directional(b)) => a == b,
+            (Light::Custom(a), Light::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(l: PointLight) -> Self {
+        Light::Point(l)
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(l: AreaLight) -> Self {
+        Light::Area(l)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(l: DirectionalLight) -> Self {
+        Light::Directional(l)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::material::Material;
+    use crate::matrix::Matrix;
+    use crate::shapes::Shape;
+    use crate::utils::ROUNDING_EPSILON;
+    use crate::utils::consts::PI;
+    use approx::assert_abs_diff_eq;
+
+    // shines along -z from everywhere at once and casts no shadows
+    #[derive(Debug)]
+    struct Headlight;
+
+    impl LightSource for Headlight {
+        fn intensity_at(&self, _point: Point, _world: &World) -> Float {
+            1.0
+        }
+
+        fn direction_to(&self, _point: Point) -> Vector {
+            Vector::new(0.0, 0.0, -1.0)
+        }
+
+        fn color(&self) -> Color {
+            Color::WHITE
+        }
+    }
+
+    #[test]
+    fn rendering_with_a_custom_light() {
+        let color = Color::new(0.8, 1.0, 0.6);
+        let mut world = World::empty();
+        world.add_light(Light::custom(Headlight));
+        world.objects.push(
+            Shape::sphere().with_material(Material::new().with_color(color).with_specular(0.0)),
+        );
+
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let image = camera.render(&world);
+
+        // facing the light head on: full ambient plus full diffuse
+        assert_abs_diff_eq!(image.pixel_at(5, 5), color, epsilon = ROUNDING_EPSILON);
+        // and darker toward the rim, where the surface turns away
+        assert!(image.pixel_at(5, 2).red < color.red);
+    }
+
+    #[test]
+    fn custom_lights_are_equal_only_to_themselves() {
+        let light = Light::custom(Headlight);
+
+        assert_eq!(light, light.clone());
+        assert_ne!(light, Light::custom(Headlight));
+        assert_eq!(light.position(), None);
+        assert_eq!(light.intensity(), Color::WHITE);
+    }
+}
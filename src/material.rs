@@ -1,21 +1,62 @@
+use crate::utils::consts::PI;
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
 use crate::color::Color;
+use crate::light::{Light, LightSource};
+use crate::matrix::Transformation;
 use crate::pattern::Pattern;
 use crate::point::Point;
-use crate::point_light::PointLight;
+use crate::sampling::GlossySampling;
 use crate::shapes::Shape;
+use crate::utils::Float;
 use crate::vector::Vector;
 
+// How the specular highlight is computed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SpecularModel {
+    // classic Phong: reflect(light) . eye
+    #[default]
+    Phong,
+    // Blinn-Phong: normal . halfway(light, eye)
+    BlinnPhong,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Material {
     pub color: Color,
     pub pattern: Option<Pattern>,
-    pub ambient: f64,
-    pub diffuse: f64,
-    pub specular: f64,
-    pub shininess: f64,
-    pub reflective: f64,
-    pub transparency: f64,
-    pub refractive_index: f64,
+    pub ambient: Float,
+    pub diffuse: Float,
+    pub specular: Float,
+    pub shininess: Float,
+    pub reflective: Float,
+    pub transparency: Float,
+    pub refractive_index: Float,
+    pub specular_model: SpecularModel,
+    // 0 reflects like a mirror; larger values blur reflections over a cone of rays
+    pub roughness: Float,
+    pub glossy_samples: usize,
+    pub glossy_sampling: GlossySampling,
+    // scale reflections by Schlick reflectance even when the material is opaque
+    pub fresnel: bool,
+    // tangent-space normals encoded as RGB, laid out by the object's uv map
+    pub normal_map: Option<Arc<Canvas>>,
+    // false hides planes and quads seen from below (against their normal)
+    pub double_sided: bool,
+    // thin-film look: the surface hue turns by this many full turns as the
+    // view goes from head-on to grazing; a gray surface has no hue to turn
+    pub iridescence: Option<Float>,
+}
+
+// The parts of shading a material that don't depend on the hit point,
+// worked out once and shared by every object with that material
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadingSetup {
+    // the pattern's inverse transform; None for a flat-colored material
+    pub pattern_inverse: Option<Transformation>,
+    // the material's color times each light's color, indexed like `World::lights`
+    pub effective_colors: Vec<Color>,
 }
 
 impl Default for Material {
@@ -30,6 +71,14 @@ impl Default for Material {
             reflective: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            specular_model: SpecularModel::Phong,
+            roughness: 0.0,
+            glossy_samples: 16,
+            glossy_sampling: GlossySampling::UniformCone,
+            fresnel: false,
+            normal_map: None,
+            double_sided: true,
+            iridescence: None,
         }
     }
 }
@@ -39,7 +88,17 @@ impl Material {
         Default::default()
     }
 
-    pub fn with_ambient(mut self, ambient: f64) -> Material {
+    // the outer sphere's material in the book's default world
+    pub fn default_world_sphere() -> Self {
+        Self {
+            color: Color::new(0.8, 1.0, 0.6),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_ambient(mut self, ambient: Float) -> Material {
         self.ambient = ambient;
 
         self
@@ -51,7 +110,7 @@ impl Material {
         self
     }
 
-    pub fn with_diffuse(mut self, diffuse: f64) -> Material {
+    pub fn with_diffuse(mut self, diffuse: Float) -> Material {
         self.diffuse = diffuse;
 
         self
@@ -63,36 +122,129 @@ impl Material {
         self
     }
 
-    pub fn with_reflective(mut self, reflective: f64) -> Material {
+    pub fn with_reflective(mut self, reflective: Float) -> Material {
         self.reflective = reflective;
 
         self
     }
 
-    pub fn with_refractive_index(mut self, index: f64) -> Material {
+    pub fn with_refractive_index(mut self, index: Float) -> Material {
         self.refractive_index = index;
 
         self
     }
 
-    pub fn with_shininess(mut self, index: f64) -> Material {
+    pub fn with_shininess(mut self, index: Float) -> Material {
         self.shininess = index;
 
         self
     }
 
-    pub fn with_specular(mut self, specular: f64) -> Material {
+    pub fn with_specular(mut self, specular: Float) -> Material {
         self.specular = specular;
 
         self
     }
 
-    pub fn with_transparency(mut self, transparency: f64) -> Material {
+    pub fn with_transparency(mut self, transparency: Float) -> Material {
         self.transparency = transparency;
 
         self
     }
 
+    pub fn with_specular_model(mut self, model: SpecularModel) -> Material {
+        self.specular_model = model;
+
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: Float) -> Material {
+        self.roughness = roughness;
+
+        self
+    }
+
+    pub fn with_glossy_samples(mut self, samples: usize) -> Material {
+        self.glossy_samples = samples;
+
+        self
+    }
+
+    pub fn with_glossy_sampling(mut self, sampling: GlossySampling) -> Material {
+        self.glossy_sampling = sampling;
+
+        self
+    }
+
+    pub fn with_fresnel(mut self, fresnel: bool) -> Material {
+        self.fresnel = fresnel;
+
+        self
+    }
+
+    pub fn with_double_sided(mut self, double_sided: bool) -> Material {
+        self.double_sided = double_sided;
+
+        self
+    }
+
+    pub fn with_iridescence(mut self, turns: Float) -> Material {
+        self.iridescence = Some(turns);
+
+        self
+    }
+
+    pub fn with_normal_map(mut self, map: Canvas) -> Material {
+        self.normal_map = Some(Arc::new(map));
+
+        self
+    }
+
+    // check that the parameters describe a physically plausible surface:
+    // coefficients in [0, 1], refractive index >= 1 and positive shininess
+    pub fn validate(&self) -> Result<(), String> {
+        let unit_ranged = [
+            ("ambient", self.ambient),
+            ("diffuse", self.diffuse),
+            ("specular", self.specular),
+            ("reflective", self.reflective),
+            ("transparency", self.transparency),
+        ];
+        for (name, value) in unit_ranged {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{name} must be in [0, 1], got {value}"));
+            }
+        }
+        if self.refractive_index.is_nan() || self.refractive_index < 1.0 {
+            return Err(format!(
+                "refractive_index must be at least 1, got {}",
+                self.refractive_index
+            ));
+        }
+        if self.shininess.is_nan() || self.shininess <= 0.0 {
+            return Err(format!(
+                "shininess must be positive, got {}",
+                self.shininess
+            ));
+        }
+        Ok(())
+    }
+
+    // pull every parameter checked by `validate` into its valid range
+    pub fn clamp_valid(&mut self) {
+        for value in [
+            &mut self.ambient,
+            &mut self.diffuse,
+            &mut self.specular,
+            &mut self.reflective,
+            &mut self.transparency,
+        ] {
+            *value = value.clamp(0.0, 1.0);
+        }
+        self.refractive_index = self.refractive_index.max(1.0);
+        self.shininess = self.shininess.max(Float::MIN_POSITIVE);
+    }
+
     // calculate the lighting at the position on the sphere using the Phong Reflection Model
     //
     // Ambient reflection is background lighting, or light reflected from other
@@ -111,21 +263,130 @@ impl Material {
         &self,
         object: &Shape,
         position: Point,
-        light: PointLight,
+        light: impl LightSource,
         eye: Vector,
         normal: Vector,
         in_shadow: bool,
+    ) -> Color {
+        let light_intensity = if in_shadow { 0.0 } else { 1.0 };
+        self.shade_with_intensity(object, position, light, eye, normal, light_intensity)
+    }
+
+    // same as `shade`, but the diffuse and specular terms are scaled by
+    // `light_intensity`, the fraction of the light reaching the point (0.0..=1.0)
+    pub fn shade_with_intensity(
+        &self,
+        object: &Shape,
+        position: Point,
+        light: impl LightSource,
+        eye: Vector,
+        normal: Vector,
+        light_intensity: Float,
     ) -> Color {
         // combine the surface color with the light's color/intensity
         let effective_color = if let Some(pattern) = &self.pattern {
             pattern.pattern_at_object(object, position)
         } else {
-            self.color * light.intensity
+            self.color * light.color()
+        };
+
+        self.shade_colored(
+            effective_color,
+            position,
+            light,
+            eye,
+            normal,
+            light_intensity,
+        )
+    }
+
+    // the hit-independent part of shading this material under `lights`;
+    // objects sharing the material can share it, see `World::material_groups`
+    pub fn shading_setup(&self, lights: &[Light]) -> ShadingSetup {
+        ShadingSetup {
+            pattern_inverse: self.pattern.as_ref().map(|p| *p.inverse_transform()),
+            effective_colors: lights.iter().map(|l| self.color * l.color()).collect(),
+        }
+    }
+
+    // the surface color lit by light `light_index` at `position`, as in
+    // `shade_with_intensity`, read through a precomputed `setup`
+    pub fn effective_color_with(
+        &self,
+        setup: &ShadingSetup,
+        light_index: usize,
+        object: &Shape,
+        position: Point,
+    ) -> Color {
+        match (&self.pattern, setup.pattern_inverse) {
+            (Some(pattern), Some(inverse)) => {
+                pattern.pattern_at_object_with(inverse, object, position)
+            }
+            (Some(pattern), None) => pattern.pattern_at_object(object, position),
+            (None, _) => setup.effective_colors[light_index],
+        }
+    }
+
+    // same as `shade_with_intensity`, for a surface whose color at `position`,
+    // combined with the light's, is already known
+    pub fn shade_colored(
+        &self,
+        effective_color: Color,
+        position: Point,
+        light: impl LightSource,
+        eye: Vector,
+        normal: Vector,
+        light_intensity: Float,
+    ) -> Color {
+        let effective_color = match self.iridescence {
+            Some(turns) => rotate_hue(effective_color, turns * (1.0 - eye.dot(normal).abs())),
+            None => effective_color,
         };
 
         // find the direction of the light source
-        let light_vector = (light.position - position).normalize();
+        let light_vector = light.direction_to(position);
+
+        self.phong(
+            effective_color,
+            light_vector,
+            eye,
+            normal,
+            light.color(),
+            light_intensity,
+        )
+    }
 
+    // the bare Phong (or Blinn-Phong) response of this material's flat color
+    // to a light of `light_color` arriving along `light_dir`, seen from
+    // `eye_dir`; no patterns, shadows or scene involved. All vectors point
+    // away from the surface.
+    pub fn evaluate(
+        &self,
+        light_dir: Vector,
+        eye_dir: Vector,
+        normal: Vector,
+        light_color: Color,
+    ) -> Color {
+        self.phong(
+            self.color * light_color,
+            light_dir.normalize(),
+            eye_dir.normalize(),
+            normal.normalize(),
+            light_color,
+            1.0,
+        )
+    }
+
+    // ambient + diffuse + specular for a surface of `effective_color`
+    fn phong(
+        &self,
+        effective_color: Color,
+        light_vector: Vector,
+        eye: Vector,
+        normal: Vector,
+        light_color: Color,
+        light_intensity: Float,
+    ) -> Color {
         // compute the ambient contribution
         let ambient = effective_color * self.ambient;
 
@@ -137,19 +398,29 @@ impl Material {
         let mut diffuse = Color::BLACK;
         let mut specular = Color::BLACK;
 
-        if light_dot_normal >= 0.0 && !in_shadow {
+        if light_dot_normal >= 0.0 && light_intensity > 0.0 {
             // compute the diffuse contribution
-            diffuse = effective_color * self.diffuse * light_dot_normal;
-
-            // reflect_dot_eye represents the cosine of the angle between the
-            // reflection vector and the eye vector. A negative number means the
-            // light reflects away from the eye.
-            let reflect_vector = -light_vector.reflect(normal);
-            let reflect_dot_eye = reflect_vector.dot(eye);
-            if reflect_dot_eye > 0.0 {
+            diffuse = effective_color * self.diffuse * light_dot_normal * light_intensity;
+
+            // the specular cosine is either reflect_dot_eye (Phong), the cosine
+            // of the angle between the reflection vector and the eye vector, or
+            // normal_dot_halfway (Blinn-Phong), the cosine of the angle between
+            // the normal and the vector halfway between light and eye.
+            // A negative number means the light reflects away from the eye.
+            let specular_cos = match self.specular_model {
+                SpecularModel::Phong => {
+                    let reflect_vector = -light_vector.reflect(normal);
+                    reflect_vector.dot(eye)
+                }
+                SpecularModel::BlinnPhong => {
+                    let halfway = (light_vector + eye).normalize();
+                    normal.dot(halfway)
+                }
+            };
+            if specular_cos > 0.0 {
                 // compute the specular contribution
-                let factor = reflect_dot_eye.powf(self.shininess);
-                specular = light.intensity * self.specular * factor;
+                let factor = specular_cos.powf(self.shininess);
+                specular = light_color * self.specular * factor * light_intensity;
             }
         }
 
@@ -158,11 +429,71 @@ impl Material {
     }
 }
 
+// turn the hue of `color` by `turns` full turns, keeping its lightness and
+// chroma, by rotating it around the lightness axis in Oklab
+fn rotate_hue(color: Color, turns: Float) -> Color {
+    let (l, a, b) = color.to_oklab();
+    let (sin, cos) = (turns * 2.0 * PI).sin_cos();
+    Color::from_oklab(l, a * cos - b * sin, a * sin + b * cos)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::point_light::PointLight;
+    use crate::utils::consts::{FRAC_PI_4, FRAC_PI_8};
     use approx::assert_abs_diff_eq;
 
+    #[test]
+    fn evaluate_matches_shading_with_the_eye_between_light_and_surface() {
+        let m = Material::new();
+        let toward_light = Vector::new(0.0, 0.0, -1.0);
+        let c = m.evaluate(toward_light, toward_light, toward_light, Color::WHITE);
+
+        assert_eq!(c, Color::new(1.9, 1.9, 1.9));
+
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE);
+        let shaded = m.shade(
+            &Shape::sphere(),
+            Point::ORIGIN,
+            light,
+            toward_light,
+            toward_light,
+            false,
+        );
+        assert_abs_diff_eq!(c, shaded);
+    }
+
+    #[test]
+    fn evaluate_ignores_patterns_and_normalizes_directions() {
+        let m = Material::new()
+            .with_color(Color::new(1.0, 0.5, 0.25))
+            .with_pattern(Pattern::striped(Color::BLACK, Color::BLACK));
+        let lit = m.evaluate(
+            Vector::new(0.0, 3.0, -3.0),
+            Vector::new(0.0, 0.0, -2.0),
+            Vector::new(0.0, 0.0, -5.0),
+            Color::WHITE,
+        );
+        let unit = m.evaluate(
+            Vector::new(0.0, 1.0, -1.0).normalize(),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Color::WHITE,
+        );
+
+        assert_abs_diff_eq!(lit, unit);
+        assert!(lit.green > 0.0);
+        // a light behind the surface leaves only ambient
+        let behind = m.evaluate(
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Color::WHITE,
+        );
+        assert_abs_diff_eq!(behind, Color::new(1.0, 0.5, 0.25) * m.ambient);
+    }
+
     #[test]
     fn default_material() {
         let m = Material::default();
@@ -190,6 +521,88 @@ mod tests {
         assert_abs_diff_eq!(m.reflective, 0.0);
     }
 
+    #[test]
+    fn default_specular_model_is_phong() {
+        assert_eq!(Material::default().specular_model, SpecularModel::Phong);
+    }
+
+    #[test]
+    fn phong_and_blinn_phong_with_eye_in_reflection_path() {
+        // light at 45 degrees above the surface, eye mirrored below it
+        let position = Point::ORIGIN;
+        let eye = Vector::new(0.0, -Float::sqrt(2.0) / 2.0, -Float::sqrt(2.0) / 2.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::WHITE);
+
+        let phong = Material::new().shade(&Shape::sphere(), position, light, eye, normal, false);
+        assert_abs_diff_eq!(phong, Color::new(1.6364, 1.6364, 1.6364));
+
+        // the halfway vector coincides with the normal, so Blinn-Phong also peaks
+        let blinn = Material::new()
+            .with_specular_model(SpecularModel::BlinnPhong)
+            .shade(&Shape::sphere(), position, light, eye, normal, false);
+        assert_abs_diff_eq!(blinn, Color::new(1.6364, 1.6364, 1.6364));
+    }
+
+    #[test]
+    fn blinn_phong_highlight_is_broader_off_peak() {
+        // eye directly in front, light at 45 degrees: the reflection vector is
+        // 45 degrees from the eye while the halfway vector is 22.5 degrees from the normal
+        let position = Point::ORIGIN;
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::WHITE);
+        let m = Material::new().with_shininess(10.0);
+
+        let phong = m.shade(&Shape::sphere(), position, light, eye, normal, false);
+        let blinn = m
+            .clone()
+            .with_specular_model(SpecularModel::BlinnPhong)
+            .shade(&Shape::sphere(), position, light, eye, normal, false);
+
+        let ambient_diffuse = 0.1 + 0.9 * Float::sqrt(2.0) / 2.0;
+        let phong_expected = ambient_diffuse + 0.9 * FRAC_PI_4.cos().powf(10.0);
+        let blinn_expected = ambient_diffuse + 0.9 * FRAC_PI_8.cos().powf(10.0);
+        assert_abs_diff_eq!(
+            phong,
+            Color::new(phong_expected, phong_expected, phong_expected)
+        );
+        assert_abs_diff_eq!(
+            blinn,
+            Color::new(blinn_expected, blinn_expected, blinn_expected)
+        );
+    }
+
+    #[test]
+    fn iridescent_hue_depends_on_the_view_angle() {
+        let flat = Material::new()
+            .with_color(Color::new(0.8, 0.3, 0.2))
+            .with_ambient(1.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let head_on = Vector::new(0.0, 0.0, -1.0);
+        let grazing = Vector::new(0.0, 0.995, -0.1).normalize();
+        let shade =
+            |m: &Material, eye| m.shade(&Shape::sphere(), Point::ORIGIN, light, eye, normal, false);
+        let hue = |c: Color| {
+            let (_, a, b) = c.to_oklab();
+            b.atan2(a)
+        };
+
+        let plain_head_on = shade(&flat, head_on);
+        assert_eq!(plain_head_on, shade(&flat, grazing));
+
+        let bubble = flat.clone().with_iridescence(0.5);
+        let front = shade(&bubble, head_on);
+        let edge = shade(&bubble, grazing);
+        assert_abs_diff_eq!(front, plain_head_on, epsilon = 1e-6);
+        assert!((hue(edge) - hue(front)).abs() > 1.0);
+        // only the hue moves
+        assert_abs_diff_eq!(edge.to_oklab().0, front.to_oklab().0, epsilon = 1e-6);
+    }
+
     #[test]
     fn lighting_with_a_pattern_applied() {
         let pattern = Pattern::striped(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0));
@@ -223,4 +636,40 @@ mod tests {
         assert_eq!(c1, Color::new(1.0, 1.0, 1.0));
         assert_eq!(c2, Color::new(0.0, 0.0, 0.0));
     }
+
+    #[test]
+    fn out_of_range_reflective_is_rejected_and_clamped() {
+        let mut m = Material::new().with_reflective(2.0);
+
+        assert_eq!(
+            m.validate(),
+            Err("reflective must be in [0, 1], got 2".to_string())
+        );
+
+        m.clamp_valid();
+        assert_abs_diff_eq!(m.reflective, 1.0);
+        assert_eq!(m.validate(), Ok(()));
+    }
+
+    #[test]
+    fn refractive_index_and_shininess_are_validated() {
+        assert_eq!(Material::default().validate(), Ok(()));
+        assert!(
+            Material::new()
+                .with_refractive_index(0.5)
+                .validate()
+                .is_err()
+        );
+        assert!(Material::new().with_shininess(0.0).validate().is_err());
+
+        let mut m = Material::new()
+            .with_refractive_index(0.5)
+            .with_shininess(-3.0)
+            .with_transparency(-0.2);
+        m.clamp_valid();
+        assert_abs_diff_eq!(m.refractive_index, 1.0);
+        assert!(m.shininess > 0.0);
+        assert_abs_diff_eq!(m.transparency, 0.0);
+        assert_eq!(m.validate(), Ok(()));
+    }
 }
@@ -1,14 +1,28 @@
-use std::ops;
+use std::{fmt, ops};
 
 use approx::AbsDiffEq;
 
 use crate::point::Point;
+use crate::utils::EPSILON;
+use crate::utils::Float;
 use crate::vector::Vector;
 
 pub type Transformation = Matrix<4>;
 
+// Returned when a transformation can't be inverted (e.g. a zero scale)
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Matrix<const N: usize>([[f64; N]; N]);
+pub struct NotInvertible;
+
+impl fmt::Display for NotInvertible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "matrix is not invertible")
+    }
+}
+
+impl std::error::Error for NotInvertible {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix<const N: usize>([[Float; N]; N]);
 
 impl<const N: usize> Matrix<N> {
     const ZERO: Self = Self([[0.0; N]; N]);
@@ -32,10 +46,15 @@ impl<const N: usize> Matrix<N> {
         }
         m
     }
+
+    // compare against `other` using a caller-chosen tolerance
+    pub fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        self.abs_diff_eq(other, epsilon)
+    }
 }
 
 impl Matrix<2> {
-    pub fn determinant(&self) -> f64 {
+    pub fn determinant(&self) -> Float {
         self[0][0] * self[1][1] - self[0][1] * self[1][0]
     }
 }
@@ -65,17 +84,21 @@ impl Matrix<3> {
         m
     }
 
-    pub fn minor(&self, row: usize, col: usize) -> f64 {
+    pub fn minor(&self, row: usize, col: usize) -> Float {
         self.submatrix(row, col).determinant()
     }
 
-    pub fn cofactor(&self, row: usize, col: usize) -> f64 {
-        let sign = if (row + col) % 2 == 0 { 1.0 } else { -1.0 };
+    pub fn cofactor(&self, row: usize, col: usize) -> Float {
+        let sign = if (row + col).is_multiple_of(2) {
+            1.0
+        } else {
+            -1.0
+        };
 
         sign * self.minor(row, col)
     }
 
-    pub fn determinant(&self) -> f64 {
+    pub fn determinant(&self) -> Float {
         let mut det = 0.0;
         for col in 0..3 {
             det += self[0][col] * self.cofactor(0, col);
@@ -109,17 +132,21 @@ impl Matrix<4> {
         m
     }
 
-    pub fn minor(&self, row: usize, col: usize) -> f64 {
+    pub fn minor(&self, row: usize, col: usize) -> Float {
         self.submatrix(row, col).determinant()
     }
 
-    pub fn cofactor(&self, row: usize, col: usize) -> f64 {
-        let sign = if (row + col) % 2 == 0 { 1.0 } else { -1.0 };
+    pub fn cofactor(&self, row: usize, col: usize) -> Float {
+        let sign = if (row + col).is_multiple_of(2) {
+            1.0
+        } else {
+            -1.0
+        };
 
         sign * self.minor(row, col)
     }
 
-    pub fn determinant(&self) -> f64 {
+    pub fn determinant(&self) -> Float {
         let mut det = 0.0;
         for col in 0..4 {
             det += self[0][col] * self.cofactor(0, col);
@@ -127,9 +154,19 @@ impl Matrix<4> {
         det
     }
 
+    pub fn is_invertible(&self) -> bool {
+        self.determinant() != 0.0
+    }
+
     pub fn inverse(&self) -> Self {
+        self.try_inverse().expect("Matrix is not invertible")
+    }
+
+    pub fn try_inverse(&self) -> Result<Self, NotInvertible> {
         let det = self.determinant();
-        assert!(!(det == 0.0), "Matrix is not invertible");
+        if det == 0.0 {
+            return Err(NotInvertible);
+        }
 
         let mut m = Self::ZERO;
 
@@ -139,10 +176,10 @@ impl Matrix<4> {
                 m[col][row] = c / det;
             }
         }
-        m
+        Ok(m)
     }
 
-    pub fn translation(x: f64, y: f64, z: f64) -> Self {
+    pub fn translation(x: Float, y: Float, z: Float) -> Self {
         Self([
             [1.0, 0.0, 0.0, x],
             [0.0, 1.0, 0.0, y],
@@ -151,7 +188,7 @@ impl Matrix<4> {
         ])
     }
 
-    pub fn scaling(x: f64, y: f64, z: f64) -> Self {
+    pub fn scaling(x: Float, y: Float, z: Float) -> Self {
         Self([
             [x, 0.0, 0.0, 0.0],
             [0.0, y, 0.0, 0.0],
@@ -160,7 +197,7 @@ impl Matrix<4> {
         ])
     }
 
-    pub fn rotation_x(radians: f64) -> Self {
+    pub fn rotation_x(radians: Float) -> Self {
         let cos_r = radians.cos();
         let sin_r = radians.sin();
 
@@ -172,7 +209,7 @@ impl Matrix<4> {
         ])
     }
 
-    pub fn rotation_y(radians: f64) -> Self {
+    pub fn rotation_y(radians: Float) -> Self {
         let cos_r = radians.cos();
         let sin_r = radians.sin();
 
@@ -184,7 +221,7 @@ impl Matrix<4> {
         ])
     }
 
-    pub fn rotation_z(radians: f64) -> Self {
+    pub fn rotation_z(radians: Float) -> Self {
         let cos_r = radians.cos();
         let sin_r = radians.sin();
 
@@ -196,7 +233,7 @@ impl Matrix<4> {
         ])
     }
 
-    pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
+    pub fn shearing(xy: Float, xz: Float, yx: Float, yz: Float, zx: Float, zy: Float) -> Self {
         Self([
             [1.0, xy, xz, 0.0],
             [yx, 1.0, yz, 0.0],
@@ -205,6 +242,21 @@ impl Matrix<4> {
         ])
     }
 
+    // mirror across the plane through the origin with the given normal
+    // (Householder reflection I - 2nn^T); the normal needn't be unit length
+    pub fn reflection(normal: Vector) -> Self {
+        let n = normal.normalize();
+        let v = [n.x, n.y, n.z];
+
+        let mut m = Self::identity();
+        for row in 0..3 {
+            for col in 0..3 {
+                m[row][col] -= 2.0 * v[row] * v[col];
+            }
+        }
+        m
+    }
+
     pub fn view_transform(from: Point, to: Point, up: Vector) -> Self {
         let forward = (to - from).normalize();
         let upn = up.normalize();
@@ -220,10 +272,43 @@ impl Matrix<4> {
 
         orientation * Self::translation(-from.x, -from.y, -from.z)
     }
+
+    // same as `self * p` for every point, but with the rows hoisted out of
+    // the loop so the compiler can vectorize it
+    pub fn transform_points(&self, points: &[Point]) -> Vec<Point> {
+        let [r0, r1, r2, _] = self.0;
+        points
+            .iter()
+            .map(|p| {
+                Point::new(
+                    r0[0] * p.x + r0[1] * p.y + r0[2] * p.z + r0[3],
+                    r1[0] * p.x + r1[1] * p.y + r1[2] * p.z + r1[3],
+                    r2[0] * p.x + r2[1] * p.y + r2[2] * p.z + r2[3],
+                )
+            })
+            .collect()
+    }
+}
+
+// one row per line with aligned columns, e.g. for println!("{m}")
+impl<const N: usize> fmt::Display for Matrix<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "|")?;
+            for value in row {
+                write!(f, " {value:>9.5}")?;
+            }
+            write!(f, " |")?;
+        }
+        Ok(())
+    }
 }
 
 impl<const N: usize> ops::Index<usize> for Matrix<N> {
-    type Output = [f64; N];
+    type Output = [Float; N];
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.0[index]
@@ -282,10 +367,10 @@ impl<const N: usize> ops::IndexMut<usize> for Matrix<N> {
 }
 
 impl<const N: usize> AbsDiffEq for Matrix<N> {
-    type Epsilon = f64;
+    type Epsilon = Float;
 
     fn default_epsilon() -> Self::Epsilon {
-        1e-4
+        EPSILON
     }
 
     fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
@@ -299,8 +384,8 @@ impl<const N: usize> AbsDiffEq for Matrix<N> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::consts::{FRAC_PI_2, FRAC_PI_4};
     use approx::{assert_abs_diff_eq, assert_abs_diff_ne};
-    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
 
     #[test]
     fn construct_4x4_matrix() {
@@ -377,6 +462,15 @@ mod tests {
         assert_abs_diff_ne!(m1, m2);
     }
 
+    #[test]
+    fn approx_eq_with_custom_epsilon() {
+        let m1 = Matrix::translation(1.0, 2.0, 3.0);
+        let m2 = Matrix::translation(1.001, 2.0, 2.999);
+
+        assert!(m1.approx_eq(&m2, 1e-2));
+        assert!(!m1.approx_eq(&m2, 1e-6));
+    }
+
     #[test]
     fn multiply_matrices() {
         let m1 = Matrix([
@@ -568,6 +662,7 @@ mod tests {
         ]);
 
         assert_abs_diff_eq!(a.determinant(), -2120.0);
+        assert!(a.is_invertible());
     }
 
     #[test]
@@ -580,6 +675,8 @@ mod tests {
         ]);
 
         assert_abs_diff_eq!(a.determinant(), 0.0);
+        assert!(!a.is_invertible());
+        assert_eq!(a.try_inverse(), Err(NotInvertible));
     }
 
     #[test]
@@ -712,7 +809,7 @@ mod tests {
 
         assert_abs_diff_eq!(
             half_quarter * p,
-            Point::new(0.0, 2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0)
+            Point::new(0.0, Float::sqrt(2.0) / 2.0, Float::sqrt(2.0) / 2.0)
         );
         assert_abs_diff_eq!(full_quarter * p, Point::new(0.0, 0.0, 1.0));
     }
@@ -724,7 +821,7 @@ mod tests {
 
         assert_abs_diff_eq!(
             half_quarter.inverse() * p,
-            Point::new(0.0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0)
+            Point::new(0.0, Float::sqrt(2.0) / 2.0, -Float::sqrt(2.0) / 2.0)
         );
     }
 
@@ -736,7 +833,7 @@ mod tests {
 
         assert_abs_diff_eq!(
             half_quarter * p,
-            Point::new(2.0_f64.sqrt() / 2.0, 0.0, 2.0_f64.sqrt() / 2.0)
+            Point::new(Float::sqrt(2.0) / 2.0, 0.0, Float::sqrt(2.0) / 2.0)
         );
         assert_abs_diff_eq!(full_quarter * p, Point::new(1.0, 0.0, 0.0));
     }
@@ -749,7 +846,7 @@ mod tests {
 
         assert_abs_diff_eq!(
             half_quarter * p,
-            Point::new(-(2.0_f64.sqrt()) / 2.0, 2.0_f64.sqrt() / 2.0, 0.0)
+            Point::new(-(Float::sqrt(2.0)) / 2.0, Float::sqrt(2.0) / 2.0, 0.0)
         );
         assert_abs_diff_eq!(full_quarter * p, Point::new(-1.0, 0.0, 0.0));
     }
@@ -881,4 +978,48 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn reflecting_across_the_xz_plane_flips_y() {
+        let m = Matrix::reflection(Vector::new(0.0, 1.0, 0.0));
+
+        assert_abs_diff_eq!(m * Point::new(2.0, 3.0, -4.0), Point::new(2.0, -3.0, -4.0));
+        assert_abs_diff_eq!(m * m, Matrix::identity());
+
+        let tilted = Matrix::reflection(Vector::new(1.0, 2.0, -2.0));
+        assert_abs_diff_eq!(tilted * tilted, Matrix::identity());
+        assert_abs_diff_eq!(tilted.determinant(), -1.0);
+    }
+
+    #[test]
+    fn transform_points_matches_multiplying_each_point() {
+        let m = Matrix::translation(1.0, -2.0, 3.0)
+            * Matrix::rotation_y(FRAC_PI_4)
+            * Matrix::scaling(2.0, 0.5, -1.0);
+        let points = [
+            Point::ORIGIN,
+            Point::new(1.0, 2.0, 3.0),
+            Point::new(-4.5, 0.25, 7.0),
+            Point::new(10.0, -10.0, 0.1),
+        ];
+
+        let batch = m.transform_points(&points);
+        assert_eq!(batch.len(), points.len());
+        for (b, &p) in batch.iter().zip(&points) {
+            assert_abs_diff_eq!(*b, m * p);
+        }
+        assert!(m.transform_points(&[]).is_empty());
+    }
+
+    #[test]
+    fn display_matrix_as_aligned_rows() {
+        let m = Matrix::translation(5.0, -3.0, 2.0);
+        let text = m.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "|   1.00000   0.00000   0.00000   5.00000 |");
+        assert_eq!(lines[1], "|   0.00000   1.00000   0.00000  -3.00000 |");
+        assert!(lines.iter().all(|l| l.len() == lines[0].len()));
+    }
 }
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::sync::Mutex;
+
+use crate::point::Point;
+use crate::utils::Float;
+
+// Gradient (Perlin) noise in roughly -1..=1, smooth and zero at every
+// integer lattice point
+pub fn perlin(p: Point) -> Float {
+    let (x0, y0, z0) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (fx, fy, fz) = (p.x - x0, p.y - y0, p.z - z0);
+    let (ix, iy, iz) = (x0 as i64, y0 as i64, z0 as i64);
+
+    // dot product of the corner's gradient with the offset to the point
+    let corner = |dx: i64, dy: i64, dz: i64| {
+        let g = GRADIENTS[(lattice_hash(ix + dx, iy + dy, iz + dz) % 12) as usize];
+        g[0] * (fx - dx as Float) + g[1] * (fy - dy as Float) + g[2] * (fz - dz as Float)
+    };
+
+    let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+    let lerp = |a: Float, b: Float, t: Float| a + (b - a) * t;
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+// fractal sum of `octaves` layers of perlin noise, each at twice the
+// frequency and half the amplitude of the one before, scaled back to -1..=1
+pub fn fbm(p: Point, octaves: usize) -> Float {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+
+    for _ in 0..octaves.max(1) {
+        sum += perlin(Point::new(
+            p.x * frequency,
+            p.y * frequency,
+            p.z * frequency,
+        )) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
+
+// the 12 edge directions of a cube
+const GRADIENTS: [[Float; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+// 6t^5 - 15t^4 + 10t^3, so the noise is smooth across lattice cells
+fn fade(t: Float) -> Float {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+// SplitMix64 finalizer over the mixed lattice coordinates
+fn lattice_hash(x: i64, y: i64, z: i64) -> u64 {
+    let mut h = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ (z as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^ (h >> 31)
+}
+
+type Cells = HashMap<[i64; 3], Float>;
+
+const SHARDS: usize = 16; // at most this many shards per `NoiseCache`
+
+// one independently locked part of a `NoiseCache`
+#[derive(Default)]
+struct Shard {
+    // cells filled since the last turnover, and the ones from before it
+    current: Cells,
+    previous: Cells,
+    hits: usize, // lookups answered without evaluating the noise
+}
+
+// Remembers noise values on a grid of `quantum`-sized cells. A point is
+// given the value at the center of its cell, so results don't depend on the
+// order points are sampled in. Cells are spread over several shards, each
+// behind its own lock, so threads sampling different cells rarely wait on
+// each other. Roughly the least recently used half of a shard is dropped once
+// it holds its share of `capacity` cells.
+pub struct NoiseCache {
+    quantum: Float,
+    capacity: usize,
+    shards: Vec<Mutex<Shard>>,
+}
+
+impl NoiseCache {
+    // panics unless `quantum` is positive and finite, since any other cell
+    // size would put every point in the same cell
+    pub fn new(quantum: Float, capacity: usize) -> Self {
+        assert!(
+            quantum > 0.0 && quantum.is_finite(),
+            "noise cache quantum must be positive and finite, got {quantum}"
+        );
+        let capacity = capacity.max(2);
+        Self {
+            quantum,
+            capacity,
+            shards: (0..(capacity / 2).min(SHARDS))
+                .map(|_| Mutex::default())
+                .collect(),
+        }
+    }
+
+    pub fn quantum(&self) -> Float {
+        self.quantum
+    }
+
+    // number of cells currently held
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let shard = shard.lock().unwrap();
+                shard.current.len() + shard.previous.len()
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // lookups so far that found their cell already cached
+    pub fn hits(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().hits)
+            .sum()
+    }
+
+    // the value of `f` at the center of the cell holding `p`, computed at
+    // most once while the cell stays cached
+    pub fn value_at(&self, p: Point, f: impl FnOnce(Point) -> Float) -> Float {
+        let q = self.quantum;
+        let key = [
+            (p.x / q).round() as i64,
+            (p.y / q).round() as i64,
+            (p.z / q).round() as i64,
+        ];
+        let index = lattice_hash(key[0], key[1], key[2]) % self.shards.len() as u64;
+        let shard = &self.shards[index as usize];
+        let limit = self.capacity / self.shards.len();
+
+        {
+            let mut shard = shard.lock().unwrap();
+            if let Some(&v) = shard.current.get(&key) {
+                shard.hits += 1;
+                return v;
+            }
+            if let Some(v) = shard.previous.remove(&key) {
+                shard.hits += 1;
+                shard.insert(key, v, limit);
+                return v;
+            }
+        }
+
+        // computed without holding the lock, so other threads aren't stalled
+        let value = f(Point::new(
+            key[0] as Float * q,
+            key[1] as Float * q,
+            key[2] as Float * q,
+        ));
+        shard.lock().unwrap().insert(key, value, limit);
+        value
+    }
+}
+
+impl Shard {
+    // add a cell, turning the generations over once this holds `limit` cells
+    fn insert(&mut self, key: [i64; 3], value: Float, limit: usize) {
+        self.current.insert(key, value);
+        if self.current.len() >= limit / 2 {
+            self.previous = mem::take(&mut self.current);
+        }
+    }
+}
+
+impl fmt::Debug for NoiseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NoiseCache")
+            .field("quantum", &self.quantum)
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+// caches hold derived values only, so two are equal when they cache alike
+impl PartialEq for NoiseCache {
+    fn eq(&self, other: &Self) -> bool {
+        self.quantum == other.quantum && self.capacity == other.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn perlin_noise_is_zero_on_the_lattice_and_bounded() {
+        assert_eq!(perlin(Point::new(3.0, -2.0, 7.0)), 0.0);
+
+        for i in 0..1000 {
+            let t = i as Float * 0.137;
+            let n = perlin(Point::new(t, t * 0.7 - 3.0, 5.0 - t * 1.3));
+            assert!((-1.5..=1.5).contains(&n), "{n}");
+        }
+        // not constant between lattice points
+        assert_ne!(
+            perlin(Point::new(0.3, 0.4, 0.5)),
+            perlin(Point::new(1.3, 0.4, 0.5))
+        );
+    }
+
+    #[test]
+    fn perlin_noise_is_continuous() {
+        let p = Point::new(0.999_999, 0.5, 0.25);
+        let q = Point::new(1.000_001, 0.5, 0.25);
+        assert_abs_diff_eq!(perlin(p), perlin(q), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn cache_evaluates_each_cell_once_at_its_center() {
+        let cache = NoiseCache::new(0.5, 64);
+        let mut calls = Vec::new();
+
+        let a = cache.value_at(Point::new(0.9, 0.1, -0.2), |c| {
+            calls.push(c);
+            1.0
+        });
+        let b = cache.value_at(Point::new(1.1, -0.1, 0.2), |c| {
+            calls.push(c);
+            2.0
+        });
+
+        assert_eq!((a, b), (1.0, 1.0));
+        assert_eq!(calls, vec![Point::new(1.0, 0.0, 0.0)]);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "quantum must be positive")]
+    fn cache_rejects_a_zero_quantum() {
+        NoiseCache::new(0.0, 16);
+    }
+
+    #[test]
+    fn cache_stays_within_capacity() {
+        let cache = NoiseCache::new(1.0, 16);
+        for i in 0..100 {
+            cache.value_at(Point::new(i as Float, 0.0, 0.0), |p| p.x);
+        }
+        assert!(cache.len() <= 16);
+
+        // recently used cells survive a turnover
+        let recent = cache.value_at(Point::new(99.0, 0.0, 0.0), |_| -1.0);
+        assert_eq!(recent, 99.0);
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::color::Color;
+use crate::material::Material;
+use crate::point::Point;
+use crate::shapes::{Mesh, Shape, Triangle};
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// A polygon face, as indices into the parsed vertex (and normal) lists
+#[derive(Debug, Clone, PartialEq)]
+pub struct Face {
+    pub vertices: Vec<usize>,
+    pub normals: Option<Vec<usize>>,
+    // the `usemtl` name in effect when the face was read
+    pub material: Option<String>,
+}
+
+// The geometry read from a Wavefront OBJ file.
+// Only `v`, `vn`, `f`, `mtllib` and `usemtl` records are understood; everything
+// else, and faces that refer to vertices or normals not read yet or that have
+// no area, is counted in `ignored`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParsedObj {
+    pub vertices: Vec<Point>,
+    pub normals: Vec<Vector>,
+    pub faces: Vec<Face>,
+    pub ignored: usize,
+    // `.mtl` files named by `mtllib`, relative to the OBJ file
+    pub material_libraries: Vec<String>,
+    // materials by name, see `with_material_library`
+    pub materials: HashMap<String, Material>,
+}
+
+impl ParsedObj {
+    pub fn parse(source: &str) -> Self {
+        let mut obj = Self::default();
+        let mut material = None;
+
+        for line in source.lines() {
+            let mut fields = line.split_whitespace();
+            let parsed = match fields.next() {
+                None => continue, // blank line
+                Some("v") => parse_floats(fields).map(|[x, y, z]| {
+                    obj.vertices.push(Point::new(x, y, z));
+                }),
+                Some("vn") => parse_floats(fields).map(|[x, y, z]| {
+                    obj.normals.push(Vector::new(x, y, z));
+                }),
+                Some("f") => parse_face(fields)
+                    .filter(|f| obj.is_valid_face(f))
+                    .map(|face| {
+                        obj.faces.push(Face {
+                            material: material.clone(),
+                            ..face
+                        })
+                    }),
+                Some("mtllib") => {
+                    let names: Vec<String> = fields.map(String::from).collect();
+                    (!names.is_empty()).then(|| obj.material_libraries.extend(names))
+                }
+                Some("usemtl") => fields.next().map(|name| material = Some(name.to_string())),
+                _ => None,
+            };
+
+            if parsed.is_none() {
+                obj.ignored += 1;
+            }
+        }
+
+        obj
+    }
+
+    // Also loads the `mtllib` libraries next to the file; one that can't be
+    // read is skipped, leaving its faces with the default material.
+    pub fn from_file(path: &str) -> Self {
+        let source = fs::read_to_string(path).expect("file should be read successfully");
+        let obj = Self::parse(&source);
+
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        let libraries: Vec<String> = obj
+            .material_libraries
+            .iter()
+            .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+            .collect();
+        libraries
+            .iter()
+            .fold(obj, |obj, library| obj.with_material_library(library))
+    }
+
+    // add the materials defined by the `.mtl` source, replacing any of the same name
+    pub fn with_material_library(mut self, source: &str) -> Self {
+        self.materials.extend(parse_mtl(source));
+        self
+    }
+
+    // the material a face's `usemtl` names, or the default if it's unknown
+    pub fn material_for(&self, face: &Face) -> Material {
+        face.material
+            .as_ref()
+            .and_then(|name| self.materials.get(name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Give every face without `vn` references smooth vertex normals, computed by
+    // averaging the normals of all such faces that share each vertex.
+    pub fn with_computed_normals(mut self) -> Self {
+        let mut sums = vec![Vector::new(0.0, 0.0, 0.0); self.vertices.len()];
+
+        for face in self.faces.iter().filter(|f| f.normals.is_none()) {
+            for [a, b, c] in fan(&face.vertices) {
+                let Some(t) = self.face_triangle(a, b, c) else {
+                    continue;
+                };
+                let n = t.normal;
+                for i in [a, b, c] {
+                    sums[i] += n;
+                }
+            }
+        }
+
+        // append the averaged normals after any normals read from the file
+        let offset = self.normals.len();
+        self.normals.extend(sums.into_iter().map(|n| {
+            if n.magnitude() > 0.0 {
+                n.normalize()
+            } else {
+                n
+            }
+        }));
+
+        for face in self.faces.iter_mut().filter(|f| f.normals.is_none()) {
+            face.normals = Some(face.vertices.iter().map(|v| v + offset).collect());
+        }
+
+        self
+    }
+
+    // triangulate every face (as a fan) into flat or smooth triangles
+    pub fn triangles(&self) -> Vec<Triangle> {
+        self.faces
+            .iter()
+            .flat_map(|face| self.face_triangles(face))
+            .collect()
+    }
+
+    // one shape per triangle, each with its face's material
+    pub fn to_shapes(&self) -> Vec<Shape> {
+        self.faces
+            .iter()
+            .flat_map(|face| {
+                let material = self.material_for(face);
+                self.face_triangles(face)
+                    .into_iter()
+                    .map(move |t| Shape::from(t).with_material(material.clone()))
+            })
+            .collect()
+    }
+
+    // all faces as a single shape, with a bounding sphere for fast misses
+    pub fn to_mesh(&self) -> Shape {
+        Shape::from(Mesh::new(self.triangles()))
+    }
+
+    // a mesh for each `usemtl` material, in the order they're first used
+    pub fn to_groups(&self) -> Vec<Shape> {
+        // each group is keyed by its first face
+        let mut groups: Vec<(&Face, Vec<Triangle>)> = Vec::new();
+
+        for face in &self.faces {
+            let index = match groups
+                .iter()
+                .position(|(first, _)| first.material == face.material)
+            {
+                Some(i) => i,
+                None => {
+                    groups.push((face, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            groups[index].1.extend(self.face_triangles(face));
+        }
+
+        groups
+            .into_iter()
+            .map(|(first, triangles)| {
+                Shape::from(Mesh::new(triangles)).with_material(self.material_for(first))
+            })
+            .collect()
+    }
+
+    // does every index of `face` refer to a vertex or normal read so far, and
+    // does every triangle of its fan have an area (and so a normal)?
+    fn is_valid_face(&self, face: &Face) -> bool {
+        let in_range = face.vertices.iter().all(|&v| v < self.vertices.len())
+            && face
+                .normals
+                .iter()
+                .flatten()
+                .all(|&n| n < self.normals.len());
+
+        in_range
+            && fan(&face.vertices).all(|[a, b, c]| {
+                self.face_triangle(a, b, c)
+                    .is_some_and(|t| t.e1.cross(t.e2).magnitude() > 0.0)
+            })
+    }
+
+    // triangles whose corners refer to missing vertices or normals are skipped
+    fn face_triangles(&self, face: &Face) -> Vec<Triangle> {
+        let corners: Vec<usize> = (0..face.vertices.len()).collect();
+        let vertex = |corner: usize| self.vertices.get(face.vertices[corner]).copied();
+        let normal = |n: &[usize], corner: usize| self.normals.get(*n.get(corner)?).copied();
+        fan(&corners)
+            .filter_map(|[a, b, c]| {
+                let (p1, p2, p3) = (vertex(a)?, vertex(b)?, vertex(c)?);
+                Some(match &face.normals {
+                    Some(n) => {
+                        Triangle::smooth(p1, p2, p3, normal(n, a)?, normal(n, b)?, normal(n, c)?)
+                    }
+                    None => Triangle::new(p1, p2, p3),
+                })
+            })
+            .collect()
+    }
+
+    fn face_triangle(&self, a: usize, b: usize, c: usize) -> Option<Triangle> {
+        Some(Triangle::new(
+            *self.vertices.get(a)?,
+            *self.vertices.get(b)?,
+            *self.vertices.get(c)?,
+        ))
+    }
+}
+
+fn parse_floats<'a>(mut fields: impl Iterator<Item = &'a str>) -> Option<[Float; 3]> {
+    let mut xyz = [0.0; 3];
+    for value in xyz.iter_mut() {
+        *value = fields.next()?.parse().ok()?;
+    }
+    Some(xyz)
+}
+
+fn parse_float<'a>(mut fields: impl Iterator<Item = &'a str>) -> Option<Float> {
+    fields.next()?.parse().ok()
+}
+
+// parse `f` records of the form `1 2 3`, `1/2/3 ...` or `1//3 ...` (1-based indices)
+fn parse_face<'a>(fields: impl Iterator<Item = &'a str>) -> Option<Face> {
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+
+    for field in fields {
+        let mut parts = field.split('/');
+        let v: usize = parts.next()?.parse().ok()?;
+        vertices.push(v.checked_sub(1)?);
+
+        if let Some(n) = parts.nth(1) {
+            let n: usize = n.parse().ok()?;
+            normals.push(n.checked_sub(1)?);
+        }
+    }
+
+    if vertices.len() < 3 || (!normals.is_empty() && normals.len() != vertices.len()) {
+        return None;
+    }
+
+    Some(Face {
+        vertices,
+        normals: if normals.is_empty() {
+            None
+        } else {
+            Some(normals)
+        },
+        material: None,
+    })
+}
+
+// The materials in a Wavefront `.mtl` library, by name. Understands `Kd`
+// (color), `Ks` (specular, as its mean), `Ns` (shininess), `d` (opacity) and
+// `Ni` (refractive index); other records are ignored.
+pub fn parse_mtl(source: &str) -> HashMap<String, Material> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Material)> = None;
+
+    for line in source.lines() {
+        let mut fields = line.split_whitespace();
+        let keyword = fields.next();
+
+        if keyword == Some("newmtl") {
+            materials.extend(current.take());
+            current = fields
+                .next()
+                .map(|name| (name.to_string(), Material::new()));
+            continue;
+        }
+
+        let Some((_, material)) = current.as_mut() else {
+            continue;
+        };
+        match keyword {
+            Some("Kd") => {
+                if let Some([r, g, b]) = parse_floats(fields) {
+                    material.color = Color::new(r, g, b);
+                }
+            }
+            Some("Ks") => {
+                if let Some([r, g, b]) = parse_floats(fields) {
+                    material.specular = (r + g + b) / 3.0;
+                }
+            }
+            Some("Ns") => {
+                if let Some(ns) = parse_float(fields) {
+                    material.shininess = ns;
+                }
+            }
+            Some("d") => {
+                if let Some(d) = parse_float(fields) {
+                    material.transparency = 1.0 - d.clamp(0.0, 1.0);
+                }
+            }
+            Some("Ni") => {
+                if let Some(ni) = parse_float(fields) {
+                    material.refractive_index = ni;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    materials.extend(current);
+    materials
+}
+
+// fan triangulation of a convex polygon: (0, 1, 2), (0, 2, 3), ...
+fn fan(indices: &[usize]) -> impl Iterator<Item = [usize; 3]> + '_ {
+    (1..indices.len().saturating_sub(1)).map(move |i| [indices[0], indices[i], indices[i + 1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright\n\
+                         who traveled much faster than light.\n\
+                         She set out one day\n\
+                         in a relative way,\n\
+                         and came back the previous night.";
+        let obj = ParsedObj::parse(gibberish);
+
+        assert_eq!(obj.ignored, 5);
+    }
+
+    #[test]
+    fn vertex_records() {
+        let obj = ParsedObj::parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0");
+
+        assert_eq!(
+            obj.vertices,
+            vec![
+                Point::new(-1.0, 1.0, 0.0),
+                Point::new(-1.0, 0.5, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let obj = ParsedObj::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4");
+        let t = obj.triangles();
+
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].p1, obj.vertices[0]);
+        assert_eq!(t[0].p2, obj.vertices[1]);
+        assert_eq!(t[0].p3, obj.vertices[2]);
+        assert_eq!(t[1].p1, obj.vertices[0]);
+        assert_eq!(t[1].p2, obj.vertices[2]);
+        assert_eq!(t[1].p3, obj.vertices[3]);
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let obj = ParsedObj::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5");
+        let t = obj.triangles();
+
+        assert_eq!(t.len(), 3);
+        assert_eq!(
+            (t[2].p1, t[2].p2, t[2].p3),
+            (obj.vertices[0], obj.vertices[3], obj.vertices[4])
+        );
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let obj = ParsedObj::parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\n\nvn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\nf 1//3 2//1 3//2\nf 1/0/3 2/102/1 3/14/2",
+        );
+        let t = obj.triangles();
+
+        assert_eq!(t[0], t[1]);
+        assert_eq!(
+            t[0].vertex_normals,
+            Some([obj.normals[2], obj.normals[0], obj.normals[1]])
+        );
+    }
+
+    #[test]
+    fn faces_referring_to_missing_vertices_are_ignored() {
+        let obj = ParsedObj::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 99\nf 1 2 3");
+
+        assert_eq!(obj.ignored, 1);
+        assert_eq!(obj.faces.len(), 1);
+        assert_eq!(obj.triangles().len(), 1);
+    }
+
+    #[test]
+    fn faces_referring_to_missing_normals_are_ignored() {
+        let obj = ParsedObj::parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 0 -1\nf 1//1 2//1 3//7\nf 1//1 2//1 3//1",
+        );
+
+        assert_eq!(obj.ignored, 1);
+        assert_eq!(obj.faces.len(), 1);
+        assert_eq!(obj.triangles().len(), 1);
+    }
+
+    #[test]
+    fn faces_without_area_are_ignored() {
+        let obj = ParsedObj::parse("v 0 0 0\nv 1 0 0\nv 2 0 0\nv 0 1 0\nf 1 2 3\nf 1 1 4")
+            .with_computed_normals();
+
+        assert_eq!(obj.ignored, 2);
+        assert!(obj.triangles().is_empty());
+        assert!(obj.normals.iter().all(|n| !n.x.is_nan()));
+    }
+
+    // a closed octahedron with outward winding
+    const OCTAHEDRON: &str = "v 1 0 0\nv -1 0 0\nv 0 1 0\nv 0 -1 0\nv 0 0 1\nv 0 0 -1\n\
+                              f 1 5 3\nf 3 5 2\nf 2 5 4\nf 4 5 1\n\
+                              f 3 6 1\nf 2 6 3\nf 4 6 2\nf 1 6 4";
+
+    #[test]
+    fn computed_normals_average_adjacent_faces() {
+        let obj = ParsedObj::parse(OCTAHEDRON);
+        let flat = obj.triangles();
+        let smooth = obj.with_computed_normals().triangles();
+
+        // every face points away from the center
+        for t in &flat {
+            assert!(t.normal.dot(t.p1 - Point::ORIGIN) > 0.0);
+        }
+
+        // each vertex is shared by four faces, whose average points along its axis
+        let first = &smooth[0];
+        let [n1, n2, n3] = first.vertex_normals.unwrap();
+        assert_abs_diff_eq!(n1, Vector::new(1.0, 0.0, 0.0));
+        assert_abs_diff_eq!(n2, Vector::new(0.0, 0.0, 1.0));
+        assert_abs_diff_eq!(n3, Vector::new(0.0, 1.0, 0.0));
+
+        for t in &flat {
+            assert!(!n1.approx_eq(&t.normal, 1e-4));
+        }
+    }
+
+    #[test]
+    fn computed_normals_keep_explicit_normals() {
+        let obj = ParsedObj::parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 0 -1\nf 1//1 2//1 3//1")
+            .with_computed_normals();
+        let t = obj.triangles();
+
+        assert_eq!(t[0].vertex_normals, Some([Vector::new(0.0, 0.0, -1.0); 3]));
+    }
+
+    const CUBE_CORNER_MTL: &str = "# two colors\n\
+                                   newmtl red\nKd 1 0 0\nKs 0.5 0.5 0.5\nNs 50\n\
+                                   newmtl glass\nKd 0.9 0.9 1\nd 0.25\nNi 1.5\n";
+
+    #[test]
+    fn parsing_material_libraries() {
+        let materials = parse_mtl(CUBE_CORNER_MTL);
+
+        let red = &materials["red"];
+        assert_eq!(red.color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(red.specular, 0.5);
+        assert_eq!(red.shininess, 50.0);
+
+        let glass = &materials["glass"];
+        assert_eq!(glass.transparency, 0.75);
+        assert_eq!(glass.refractive_index, 1.5);
+    }
+
+    #[test]
+    fn faces_carry_the_material_named_by_usemtl() {
+        let dir = std::env::temp_dir().join(format!("obj_mtl_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("corner.mtl"), CUBE_CORNER_MTL).unwrap();
+        fs::write(
+            dir.join("corner.obj"),
+            "mtllib corner.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\n\
+             f 1 2 3\nusemtl red\nf 1 3 4\nf 1 4 2\nusemtl glass\nf 2 3 4\n",
+        )
+        .unwrap();
+
+        let obj = ParsedObj::from_file(dir.join("corner.obj").to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(obj.material_libraries, vec!["corner.mtl"]);
+        assert_eq!(obj.faces[0].material, None);
+        assert_eq!(obj.faces[1].material.as_deref(), Some("red"));
+
+        let groups = obj.to_groups();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].material(), &Material::default());
+        assert_eq!(groups[1].material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(groups[2].material().transparency, 0.75);
+
+        let shapes = obj.to_shapes();
+        assert_eq!(shapes[1].material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(shapes[2].material().color, Color::new(1.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::utils::Float;
+// Schlick's approximation of the fraction of light reflected at the boundary
+// from a medium with index `n1` into one with index `n2`, where `cos_theta` is
+// the cosine of the angle between the incoming ray and the surface normal.
+// Returns 1.0 under total internal reflection.
+pub fn fresnel_schlick(cos_theta: Float, n1: Float, n2: Float) -> Float {
+    let mut cos = cos_theta;
+
+    // total internal reflection can only occur if n1 > n2
+    if n1 > n2 {
+        let n_ratio = n1 / n2;
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos.powi(2));
+        if sin2_t > 1.0 {
+            return 1.0;
+        }
+
+        // when n1 > n2, use cos(theta_t) instead
+        cos = (1.0 - sin2_t).sqrt();
+    }
+
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let cos = Float::sqrt(2.0) / 2.0;
+
+        assert_abs_diff_eq!(fresnel_schlick(cos, 1.5, 1.0), 1.0);
+    }
+
+    #[test]
+    fn schlick_with_a_perpendicular_viewing_angle() {
+        assert_abs_diff_eq!(fresnel_schlick(1.0, 1.0, 1.5), 0.04);
+    }
+
+    #[test]
+    fn schlick_with_small_angle_and_n2_greater_than_n1() {
+        // a ray grazing a glass sphere at y = 0.99; the book's 0.48873 comes
+        // from a hit point rounded to t = 1.8589, hence the looser tolerance
+        let cos = (1.0 - (0.99 as Float).powi(2)).sqrt();
+
+        assert_abs_diff_eq!(fresnel_schlick(cos, 1.0, 1.5), 0.48873, epsilon = 1e-4);
+    }
+}
@@ -1,9 +1,14 @@
 // FUTURE TODO:  Add support for blended patterns, more UV patterns, and noise jittered patterns
 
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
 use crate::color::Color;
 use crate::matrix::Transformation;
+use crate::noise::{NoiseCache, fbm};
 use crate::point::Point;
 use crate::shapes::Shape;
+use crate::utils::Float;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Source {
@@ -22,13 +27,64 @@ impl From<Pattern> for Source {
     }
 }
 
+// Color space used when blending the two endpoints of a gradient
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GradientSpace {
+    #[default]
+    LinearRgb,
+    Oklab,
+}
+
+// The space a pattern's transform is applied in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PatternSpace {
+    // the pattern moves with the object it's on
+    #[default]
+    Object,
+    // the pattern stays put in the world and objects slide through it
+    World,
+}
+
+// A coordinate axis, for patterns that vary along one direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum PatternType {
     Striped,
-    Gradient,
+    Gradient {
+        space: GradientSpace,
+    },
     Ring,
     Checker,
-    CheckerUV { width: f64, height: f64 },
+    CheckerUV {
+        width: Float,
+        height: Float,
+    },
+    UvImage {
+        image: Arc<Canvas>,
+    },
+    // one pattern per cube face: front, back, left, right, up, down
+    CubeMap {
+        faces: Box<[Pattern; 6]>,
+    },
+    // colors interpolated between (position, color) stops sorted by position
+    Ramp {
+        stops: Vec<(Float, Color)>,
+        axis: Axis,
+    },
+    // the object's uv coordinates as red (u) and green (v)
+    UvDebug,
+    // a blend of a and b by fractal perlin noise, optionally cached, see
+    // `with_cache`
+    Noise {
+        octaves: usize,
+        cache: Option<Arc<NoiseCache>>,
+    },
     Test,
 }
 
@@ -36,6 +92,7 @@ pub enum PatternType {
 pub struct Pattern {
     transform: Transformation,
     inverse_transform: Transformation,
+    space: PatternSpace,
     pattern_type: PatternType,
     a: Source,
     b: Source,
@@ -46,6 +103,7 @@ impl Pattern {
         Self {
             transform: Transformation::identity(),
             inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
             pattern_type: PatternType::Striped,
             a: a.into(),
             b: b.into(),
@@ -57,7 +115,10 @@ impl Pattern {
         Self {
             transform: Transformation::identity(),
             inverse_transform: Transformation::identity(),
-            pattern_type: PatternType::Gradient,
+            space: PatternSpace::Object,
+            pattern_type: PatternType::Gradient {
+                space: GradientSpace::LinearRgb,
+            },
             a: a.into(),
             b: b.into(),
         }
@@ -68,6 +129,7 @@ impl Pattern {
         Self {
             transform: Transformation::identity(),
             inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
             pattern_type: PatternType::Ring,
             a: a.into(),
             b: b.into(),
@@ -79,6 +141,7 @@ impl Pattern {
         Self {
             transform: Transformation::identity(),
             inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
             pattern_type: PatternType::Checker,
             a: a.into(),
             b: b.into(),
@@ -86,25 +149,102 @@ impl Pattern {
     }
 
     pub fn checker_uv<A: Into<Source>, B: Into<Source>>(
-        width: f64,
-        height: f64,
+        width: Float,
+        height: Float,
         a: A,
         b: B,
     ) -> Self {
         Self {
             transform: Transformation::identity(),
             inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
             pattern_type: PatternType::CheckerUV { width, height },
             a: a.into(),
             b: b.into(),
         }
     }
 
+    // texture an object with an image using the object's uv_map
+    pub fn uv_image(image: Canvas) -> Self {
+        Self {
+            transform: Transformation::identity(),
+            inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
+            pattern_type: PatternType::UvImage {
+                image: Arc::new(image),
+            },
+            a: Color::WHITE.into(),
+            b: Color::BLACK.into(),
+        }
+    }
+
+    // shows the object's uv mapping: red grows with u, green with v
+    pub fn uv_debug() -> Self {
+        Self {
+            transform: Transformation::identity(),
+            inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
+            pattern_type: PatternType::UvDebug,
+            a: Color::BLACK.into(),
+            b: Color::BLACK.into(),
+        }
+    }
+
+    // texture each face of a cube with its own pattern, sampled with face-local uv
+    pub fn cube_map(
+        front: Pattern,
+        back: Pattern,
+        left: Pattern,
+        right: Pattern,
+        up: Pattern,
+        down: Pattern,
+    ) -> Self {
+        Self {
+            transform: Transformation::identity(),
+            inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
+            pattern_type: PatternType::CubeMap {
+                faces: Box::new([front, back, left, right, up, down]),
+            },
+            a: Color::WHITE.into(),
+            b: Color::BLACK.into(),
+        }
+    }
+
+    // a multi-stop gradient along `axis`; `stops` must be sorted by position
+    // and points beyond either end take the end color
+    pub fn ramp(stops: Vec<(Float, Color)>, axis: Axis) -> Self {
+        Self {
+            transform: Transformation::identity(),
+            inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
+            pattern_type: PatternType::Ramp { stops, axis },
+            a: Color::WHITE.into(),
+            b: Color::BLACK.into(),
+        }
+    }
+
+    // a and b mixed by `octaves` layers of perlin noise, e.g. for marble or clouds
+    pub fn noise<A: Into<Source>, B: Into<Source>>(a: A, b: B, octaves: usize) -> Self {
+        Self {
+            transform: Transformation::identity(),
+            inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
+            pattern_type: PatternType::Noise {
+                octaves,
+                cache: None,
+            },
+            a: a.into(),
+            b: b.into(),
+        }
+    }
+
     pub fn test() -> Self {
         /* ... */
         Self {
             transform: Transformation::identity(),
             inverse_transform: Transformation::identity(),
+            space: PatternSpace::Object,
             pattern_type: PatternType::Test,
             a: Color::WHITE.into(),
             b: Color::BLACK.into(),
@@ -121,10 +261,42 @@ impl Pattern {
         &self.transform
     }
 
+    pub fn inverse_transform(&self) -> &Transformation {
+        &self.inverse_transform
+    }
+
+    pub fn with_space(mut self, space: PatternSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn space(&self) -> PatternSpace {
+        self.space
+    }
+
     pub fn with_type(mut self, pattern_type: PatternType) -> Self {
         self.pattern_type = pattern_type;
         self
     }
+
+    // Select the interpolation space for a gradient; no-op for other pattern types
+    pub fn with_gradient_space(mut self, space: GradientSpace) -> Self {
+        if let PatternType::Gradient { space: s } = &mut self.pattern_type {
+            *s = space;
+        }
+        self
+    }
+
+    // Remember noise values on a grid of `quantum`-sized cells in object
+    // space, trading exactness for speed in static scenes; no-op for other
+    // pattern types. Clones of the pattern share the cache. Panics unless
+    // `quantum` is positive and finite.
+    pub fn with_cache(mut self, quantum: Float) -> Self {
+        if let PatternType::Noise { cache, .. } = &mut self.pattern_type {
+            *cache = Some(Arc::new(NoiseCache::new(quantum, NOISE_CACHE_CAPACITY)));
+        }
+        self
+    }
 }
 
 impl Pattern {
@@ -136,11 +308,12 @@ impl Pattern {
     }
 }
 
-const EPS_PLANE: f64 = 1e-6; // near-planar threshold for |y|
-const EPS_FLOOR: f64 = 1e-9; // bias to avoid underflow right below integers
+const NOISE_CACHE_CAPACITY: usize = 4096; // cells kept by `with_cache`
+const EPS_PLANE: Float = 1e-6; // near-planar threshold for |y|
+const EPS_FLOOR: Float = 1e-9; // bias to avoid underflow right below integers
 
 #[inline]
-fn floor_eps(x: f64) -> i32 {
+fn floor_eps(x: Float) -> i32 {
     // Bias toward the “current” cell so x = 1.000000000 - 1e-15 doesn’t floor to 0
     let bias = if x >= 0.0 { EPS_FLOOR } else { -EPS_FLOOR };
     (x + bias).floor() as i32
@@ -148,21 +321,51 @@ fn floor_eps(x: f64) -> i32 {
 
 impl Pattern {
     pub fn pattern_at_object(&self, object: &Shape, point: Point) -> Color {
-        let object_point = *object.inverse_transform() * point;
-        let pattern_point = self.inverse_transform * object_point;
+        self.pattern_at_object_with(self.inverse_transform, object, point)
+    }
+
+    // as `pattern_at_object`, with this pattern's inverse transform passed in,
+    // e.g. from a material group's shared `ShadingSetup`
+    pub fn pattern_at_object_with(
+        &self,
+        inverse_transform: Transformation,
+        object: &Shape,
+        point: Point,
+    ) -> Color {
+        let object_point = self.anchor_point(object, point);
+        let pattern_point = inverse_transform * object_point;
 
         match &self.pattern_type {
             PatternType::Striped => self.stripe_at(pattern_point, object),
-            PatternType::Gradient => self.gradient_at(pattern_point, object),
+            PatternType::Gradient { space } => self.gradient_at(pattern_point, object, *space),
             PatternType::Ring => self.ring_at(pattern_point, object),
             PatternType::Checker => self.checker_at(pattern_point, object),
             PatternType::CheckerUV { width, height } => {
                 self.checker_uv_at(pattern_point, object, *width, *height)
             }
+            PatternType::UvImage { image } => self.uv_image_at(pattern_point, object, image),
+            PatternType::CubeMap { faces } => {
+                let face = CubeFace::of(pattern_point);
+                let (u, v) = face.uv(pattern_point);
+                faces[face as usize].pattern_at_uv(object, point, u, v)
+            }
+            PatternType::Ramp { stops, axis } => ramp_at(stops, *axis, pattern_point),
+            PatternType::UvDebug => self.uv_debug_at(pattern_point, object),
+            PatternType::Noise { octaves, cache } => {
+                self.noise_at(object_point, object, *octaves, cache.as_deref())
+            }
             PatternType::Test => Color::new(pattern_point.x, pattern_point.y, pattern_point.z),
         }
     }
 
+    // `point` in the space the pattern's transform applies to
+    fn anchor_point(&self, object: &Shape, point: Point) -> Point {
+        match self.space {
+            PatternSpace::Object => *object.inverse_transform() * point,
+            PatternSpace::World => point,
+        }
+    }
+
     fn stripe_at(&self, p: Point, obj: &Shape) -> Color {
         if (p.x.floor() as i32) % 2 == 0 {
             self.sample_source(&self.a, p, obj)
@@ -171,11 +374,40 @@ impl Pattern {
         }
     }
 
-    fn gradient_at(&self, p: Point, obj: &Shape) -> Color {
+    fn gradient_at(&self, p: Point, obj: &Shape, space: GradientSpace) -> Color {
         let ca = self.sample_source(&self.a, p, obj);
         let cb = self.sample_source(&self.b, p, obj);
         let t = p.x - p.x.floor();
-        ca + (cb - ca) * t
+
+        match space {
+            GradientSpace::LinearRgb => Color::lerp(ca, cb, t),
+            GradientSpace::Oklab => {
+                // blend in a perceptual space to avoid muddy midtones
+                let (la, aa, ba) = ca.to_oklab();
+                let (lb, ab, bb) = cb.to_oklab();
+                Color::from_oklab(la + (lb - la) * t, aa + (ab - aa) * t, ba + (bb - ba) * t)
+            }
+        }
+    }
+
+    // cached by object-space point, since that's what stays put between renders
+    fn noise_at(
+        &self,
+        object_point: Point,
+        obj: &Shape,
+        octaves: usize,
+        cache: Option<&NoiseCache>,
+    ) -> Color {
+        let noise = |p: Point| fbm(self.inverse_transform * p, octaves);
+        let value = match cache {
+            Some(cache) => cache.value_at(object_point, noise),
+            None => noise(object_point),
+        };
+
+        let p = self.inverse_transform * object_point;
+        let ca = self.sample_source(&self.a, p, obj);
+        let cb = self.sample_source(&self.b, p, obj);
+        Color::lerp(ca, cb, ((value + 1.0) / 2.0).clamp(0.0, 1.0))
     }
 
     fn ring_at(&self, p: Point, obj: &Shape) -> Color {
@@ -206,27 +438,166 @@ impl Pattern {
         }
     }
 
-    fn checker_uv_at(&self, p: Point, obj: &Shape, width: f64, height: f64) -> Color {
+    fn checker_uv_at(&self, p: Point, obj: &Shape, width: Float, height: Float) -> Color {
         if let Some(uv_fn) = obj.uv_map() {
             let (u, v) = uv_fn(p);
+            self.checker_uv_color(p, obj, u, v, width, height)
+        } else {
+            // Fallback if no uv_map
+            self.sample_source(&self.a, p, obj)
+        }
+    }
 
-            // Clamp slightly inside [0,1) to avoid landing exactly on the top/right edge.
-            let u = u.clamp(0.0, 1.0 - EPS_FLOOR) * width;
-            let v = v.clamp(0.0, 1.0 - EPS_FLOOR) * height;
+    fn checker_uv_color(
+        &self,
+        p: Point,
+        obj: &Shape,
+        u: Float,
+        v: Float,
+        width: Float,
+        height: Float,
+    ) -> Color {
+        // Same biased floor as the 3D checker, then keep u = 1 (the seam) and
+        // v = 1 (the pole) in the last cell rather than a phantom one past it.
+        let cell = |t: Float, cells: Float| {
+            floor_eps(t.clamp(0.0, 1.0) * cells).clamp(0, (cells.ceil() as i32 - 1).max(0))
+        };
+        let ix = cell(u, width);
+        let iy = cell(v, height);
 
-            let ix = floor_eps(u);
-            let iy = floor_eps(v);
+        if (ix + iy) % 2 == 0 {
+            self.sample_source(&self.a, p, obj)
+        } else {
+            self.sample_source(&self.b, p, obj)
+        }
+    }
 
-            if (ix + iy) % 2 == 0 {
-                self.sample_source(&self.a, p, obj)
-            } else {
-                self.sample_source(&self.b, p, obj)
-            }
+    fn uv_image_at(&self, p: Point, obj: &Shape, image: &Canvas) -> Color {
+        if let Some(uv_fn) = obj.uv_map() {
+            let (u, v) = uv_fn(p);
+            sample_bilinear_wrapped(image, u, v)
         } else {
             // Fallback if no uv_map
             self.sample_source(&self.a, p, obj)
         }
     }
+
+    fn uv_debug_at(&self, p: Point, obj: &Shape) -> Color {
+        if let Some(uv_fn) = obj.uv_map() {
+            let (u, v) = uv_fn(p);
+            Color::new(u, v, 0.0)
+        } else {
+            // Fallback if no uv_map
+            self.sample_source(&self.a, p, obj)
+        }
+    }
+
+    // Evaluate with uv supplied by the caller instead of the object's uv_map.
+    // Patterns that aren't uv based ignore (u, v) and use the point as usual.
+    fn pattern_at_uv(&self, object: &Shape, point: Point, u: Float, v: Float) -> Color {
+        let pattern_point = self.inverse_transform * self.anchor_point(object, point);
+
+        match &self.pattern_type {
+            PatternType::CheckerUV { width, height } => {
+                self.checker_uv_color(pattern_point, object, u, v, *width, *height)
+            }
+            PatternType::UvImage { image } => sample_bilinear_wrapped(image, u, v),
+            PatternType::UvDebug => Color::new(u, v, 0.0),
+            _ => self.pattern_at_object(object, point),
+        }
+    }
+}
+
+// The face of a cube centered at the origin that a point lies on (or nearest to)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CubeFace {
+    Front,
+    Back,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    // the face is chosen by the coordinate with the largest magnitude
+    fn of(p: Point) -> Self {
+        let (ax, ay, az) = (p.x.abs(), p.y.abs(), p.z.abs());
+        let coord = ax.max(ay).max(az);
+
+        if coord == p.x {
+            CubeFace::Right
+        } else if coord == -p.x {
+            CubeFace::Left
+        } else if coord == p.y {
+            CubeFace::Up
+        } else if coord == -p.y {
+            CubeFace::Down
+        } else if coord == p.z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    // face-local uv in [0, 1), as seen looking at the face from outside the cube
+    fn uv(self, p: Point) -> (Float, Float) {
+        let wrap = |x: Float| x.rem_euclid(2.0) / 2.0;
+
+        match self {
+            CubeFace::Front => (wrap(p.x + 1.0), wrap(p.y + 1.0)),
+            CubeFace::Back => (wrap(1.0 - p.x), wrap(p.y + 1.0)),
+            CubeFace::Left => (wrap(p.z + 1.0), wrap(p.y + 1.0)),
+            CubeFace::Right => (wrap(1.0 - p.z), wrap(p.y + 1.0)),
+            CubeFace::Up => (wrap(p.x + 1.0), wrap(1.0 - p.z)),
+            CubeFace::Down => (wrap(p.x + 1.0), wrap(p.z + 1.0)),
+        }
+    }
+}
+
+fn ramp_at(stops: &[(Float, Color)], axis: Axis, p: Point) -> Color {
+    let x = match axis {
+        Axis::X => p.x,
+        Axis::Y => p.y,
+        Axis::Z => p.z,
+    };
+
+    match stops.iter().position(|&(at, _)| at > x) {
+        None => stops.last().map_or(Color::BLACK, |&(_, c)| c),
+        Some(0) => stops[0].1,
+        Some(i) => {
+            let (x0, c0) = stops[i - 1];
+            let (x1, c1) = stops[i];
+            Color::lerp(c0, c1, (x - x0) / (x1 - x0))
+        }
+    }
+}
+
+// Bilinearly sample `image` at (u, v), with v = 0 at the bottom row.
+// u wraps around so texels on either side of the u = 0/1 seam blend
+// together instead of producing a hard vertical line; v is clamped.
+pub(crate) fn sample_bilinear_wrapped(image: &Canvas, u: Float, v: Float) -> Color {
+    let w = image.width as isize;
+    let h = image.height as isize;
+
+    // texel centers sit at half-integer coordinates
+    let x = u * image.width as Float - 0.5;
+    let y = (1.0 - v) * image.height as Float - 0.5;
+
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let tx = x - x0;
+    let ty = y - y0;
+
+    let wrap_x = |x: isize| x.rem_euclid(w) as usize;
+    let clamp_y = |y: isize| y.clamp(0, h - 1) as usize;
+
+    let (xa, xb) = (wrap_x(x0 as isize), wrap_x(x0 as isize + 1));
+    let (ya, yb) = (clamp_y(y0 as isize), clamp_y(y0 as isize + 1));
+
+    let top = image.pixel_at(xa, ya) * (1.0 - tx) + image.pixel_at(xb, ya) * tx;
+    let bottom = image.pixel_at(xa, yb) * (1.0 - tx) + image.pixel_at(xb, yb) * tx;
+    top * (1.0 - ty) + bottom * ty
 }
 
 #[cfg(test)]
@@ -235,6 +606,7 @@ mod tests {
     use crate::matrix::Transformation;
     use crate::shapes::Shape;
     use crate::shapes::Sphere;
+    use approx::assert_abs_diff_eq;
 
     use super::*;
 
@@ -360,23 +732,48 @@ mod tests {
         let sphere = Shape::from(Sphere::new());
 
         assert_eq!(
-            pattern.gradient_at(Point::new(0.0, 0.0, 0.0), &sphere),
+            pattern.gradient_at(Point::new(0.0, 0.0, 0.0), &sphere, GradientSpace::LinearRgb),
             Color::WHITE
         );
         assert_eq!(
-            pattern.gradient_at(Point::new(0.25, 0.0, 0.0), &sphere),
+            pattern.gradient_at(
+                Point::new(0.25, 0.0, 0.0),
+                &sphere,
+                GradientSpace::LinearRgb
+            ),
             Color::new(0.75, 0.75, 0.75)
         );
         assert_eq!(
-            pattern.gradient_at(Point::new(0.5, 0.0, 0.0), &sphere),
+            pattern.gradient_at(Point::new(0.5, 0.0, 0.0), &sphere, GradientSpace::LinearRgb),
             Color::new(0.5, 0.5, 0.5)
         );
         assert_eq!(
-            pattern.gradient_at(Point::new(0.75, 0.0, 0.0), &sphere),
+            pattern.gradient_at(
+                Point::new(0.75, 0.0, 0.0),
+                &sphere,
+                GradientSpace::LinearRgb
+            ),
             Color::new(0.25, 0.25, 0.25)
         );
     }
 
+    #[test]
+    fn oklab_gradient_midpoint_is_more_saturated() {
+        let linear = Pattern::gradient(Color::RED, Color::GREEN);
+        let oklab =
+            Pattern::gradient(Color::RED, Color::GREEN).with_gradient_space(GradientSpace::Oklab);
+        let sphere = Shape::from(Sphere::new());
+        let mid = Point::new(0.5, 0.0, 0.0);
+
+        // RGB chroma: distance of the color from the neutral gray axis
+        let chroma = |c: Color| c.red.max(c.green).max(c.blue) - c.red.min(c.green).min(c.blue);
+
+        let c_linear = linear.pattern_at_object(&sphere, mid);
+        let c_oklab = oklab.pattern_at_object(&sphere, mid);
+
+        assert!(chroma(c_oklab) > chroma(c_linear) + 0.1);
+    }
+
     #[test]
     fn ring_test() {
         let pattern = Pattern::ring(Color::WHITE, Color::BLACK);
@@ -457,6 +854,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn uv_image_blends_across_the_spherical_seam() {
+        // leftmost column red, rightmost column blue: the two meet at the u = 0/1 seam
+        let mut image = Canvas::empty(4, 2);
+        for y in 0..2 {
+            image.write_pixel(0, y, Color::RED);
+            image.write_pixel(3, y, Color::BLUE);
+        }
+        let pattern = Pattern::uv_image(image);
+        let sphere = Shape::from(Sphere::new());
+
+        // spherical_map puts the seam at -z; +x lands at u ~ 0, -x at u ~ 1
+        let right = pattern.pattern_at_object(&sphere, Point::new(1e-4, 0.0, -1.0));
+        let left = pattern.pattern_at_object(&sphere, Point::new(-1e-4, 0.0, -1.0));
+
+        let purple = Color::new(0.5, 0.0, 0.5);
+        assert_abs_diff_eq!(right, purple, epsilon = 1e-3);
+        assert_abs_diff_eq!(left, purple, epsilon = 1e-3);
+    }
+
     // #[test]
     // fn checker_pattern_in_2d() {
     //     let pattern = Pattern::checker_uv(2.0, 2.0, Color::BLACK, Color::WHITE);
@@ -512,4 +929,199 @@ mod tests {
             Color::BLACK
         );
     }
+
+    #[test]
+    fn uv_checkers_are_stable_at_the_edges_of_uv_space() {
+        let sphere = Shape::sphere();
+        let single = Pattern::checker_uv(1.0, 1.0, Color::BLACK, Color::WHITE);
+        for t in [0.0, 1e-12, 0.5, 1.0 - 1e-12, 1.0] {
+            assert_eq!(
+                single.pattern_at_uv(&sphere, Point::ORIGIN, t, 0.5),
+                Color::BLACK
+            );
+            assert_eq!(
+                single.pattern_at_uv(&sphere, Point::ORIGIN, 0.5, t),
+                Color::BLACK
+            );
+        }
+
+        let pattern = Pattern::checker_uv(16.0, 8.0, Color::BLACK, Color::WHITE);
+        let at = |u: Float, v: Float| pattern.pattern_at_uv(&sphere, Point::ORIGIN, u, v);
+        for v in [0.0, 0.3, 1.0] {
+            // either side of the seam keeps the color of its own column
+            assert_eq!(at(0.0, v), at(1e-12, v));
+            assert_eq!(at(1.0, v), at(1.0 - 1e-12, v));
+            assert_ne!(at(0.0, v), at(1.0, v));
+        }
+        for u in [0.0, 0.3, 1.0] {
+            assert_eq!(at(u, 0.0), at(u, 1e-12));
+            assert_eq!(at(u, 1.0), at(u, 1.0 - 1e-12));
+        }
+    }
+
+    #[test]
+    fn uv_checkers_are_stable_at_the_poles_of_a_sphere() {
+        let pattern = Pattern::checker_uv(16.0, 8.0, Color::BLACK, Color::WHITE);
+        let sphere = Shape::sphere();
+        let at = |p: Point| pattern.pattern_at_object(&sphere, p);
+
+        // v = 1 at the north pole lands in the top row, v = 0 in the bottom
+        let north = at(Point::new(0.0, 1.0, 0.0));
+        let south = at(Point::new(0.0, -1.0, 0.0));
+        assert_eq!(north, at(Point::new(0.0, 1.0 - 1e-12, 1e-6)));
+        assert_eq!(south, at(Point::new(0.0, -1.0 + 1e-12, 1e-6)));
+        assert_ne!(north, south);
+    }
+
+    #[test]
+    fn world_space_checkers_ignore_the_object_transform() {
+        let pattern = Pattern::checker(Color::WHITE, Color::BLACK).with_space(PatternSpace::World);
+        let point = Point::new(1.5, 0.25, 0.5);
+        let expected = pattern.pattern_at_object(&Shape::sphere(), point);
+
+        for t in [
+            Transformation::translation(1.0, 0.0, 0.0),
+            Transformation::scaling(0.5, 0.5, 0.5),
+            Transformation::translation(0.0, 0.0, 1.0),
+        ] {
+            let sphere = Shape::sphere().with_transform(t);
+            assert_eq!(pattern.pattern_at_object(&sphere, point), expected);
+
+            // anchored to the object, the same point changes color
+            let object_space = pattern.clone().with_space(PatternSpace::Object);
+            assert_ne!(object_space.pattern_at_object(&sphere, point), expected);
+        }
+    }
+
+    fn solid(c: Color) -> Pattern {
+        Pattern::striped(c, c)
+    }
+
+    fn dice() -> Pattern {
+        Pattern::cube_map(
+            solid(Color::RED),
+            solid(Color::YELLOW),
+            solid(Color::ORANGE),
+            solid(Color::GREEN),
+            solid(Color::BLUE),
+            solid(Color::WHITE),
+        )
+    }
+
+    #[test]
+    fn cube_map_selects_the_pattern_of_the_hit_face() {
+        use crate::ray::Ray;
+        use crate::vector::Vector;
+
+        let pattern = dice();
+        let sphere = Shape::from(Sphere::new());
+        let color_seen_by = |ray: Ray| {
+            let xs = sphere.intersect(ray);
+            let hit = xs.hit().unwrap();
+            pattern.pattern_at_object(&sphere, ray.position(hit.t))
+        };
+
+        // the +x face is "right"
+        let r = Ray::new(Point::new(5.0, 0.2, 0.1), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(color_seen_by(r), Color::GREEN);
+
+        // the -y face is "down"
+        let r = Ray::new(Point::new(0.1, -5.0, -0.2), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(color_seen_by(r), Color::WHITE);
+    }
+
+    #[test]
+    fn cube_map_faces_use_face_local_uv() {
+        let checkers = Pattern::checker_uv(2.0, 2.0, Color::BLACK, Color::WHITE);
+        let pattern = Pattern::cube_map(
+            checkers.clone(),
+            checkers.clone(),
+            checkers.clone(),
+            checkers.clone(),
+            checkers.clone(),
+            checkers,
+        );
+        let sphere = Shape::from(Sphere::new());
+
+        // the front face's lower left quarter is (u, v) = (0.25, 0.25)
+        let c = pattern.pattern_at_object(&sphere, Point::new(-0.5, -0.5, 1.0));
+        assert_eq!(c, Color::BLACK);
+        let c = pattern.pattern_at_object(&sphere, Point::new(0.5, -0.5, 1.0));
+        assert_eq!(c, Color::WHITE);
+
+        assert_eq!(CubeFace::Up.uv(Point::new(-0.5, 1.0, -0.5)), (0.25, 0.75));
+        assert_eq!(CubeFace::Down.uv(Point::new(-0.5, -1.0, 0.5)), (0.25, 0.75));
+        assert_eq!(CubeFace::Right.uv(Point::new(1.0, 0.5, 0.5)), (0.25, 0.75));
+    }
+
+    #[test]
+    fn ramp_interpolates_between_stops_and_clamps_at_the_ends() {
+        let pattern = Pattern::ramp(
+            vec![(0.0, Color::BLUE), (1.0, Color::GREEN), (3.0, Color::WHITE)],
+            Axis::Y,
+        );
+        let sphere = Shape::from(Sphere::new());
+        let at = |y: Float| pattern.pattern_at_object(&sphere, Point::new(5.0, y, -2.0));
+
+        assert_abs_diff_eq!(at(0.5), Color::new(0.0, 0.5, 0.5));
+        assert_abs_diff_eq!(at(2.5), Color::new(0.75, 1.0, 0.75));
+        assert_eq!(at(1.0), Color::GREEN);
+        assert_eq!(at(-4.0), Color::BLUE);
+        assert_eq!(at(10.0), Color::WHITE);
+    }
+
+    #[test]
+    fn uv_debug_colors_a_sphere_by_its_uv() {
+        let sphere = Shape::sphere();
+        let pattern = Pattern::uv_debug();
+
+        assert_abs_diff_eq!(
+            pattern.pattern_at_object(&sphere, Point::new(0.0, 0.0, -1.0)),
+            Color::new(0.0, 0.5, 0.0)
+        );
+        assert_abs_diff_eq!(
+            pattern.pattern_at_object(&sphere, Point::new(1.0, 0.0, 0.0)),
+            Color::new(0.25, 0.5, 0.0)
+        );
+        assert_abs_diff_eq!(
+            pattern.pattern_at_object(&sphere, Point::new(0.0, 1.0, 0.0)),
+            Color::new(0.5, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn cached_noise_stays_close_to_uncached_noise() {
+        let sphere = Shape::sphere();
+        let exact = Pattern::noise(Color::BLACK, Color::WHITE, 4);
+        let cached = exact.clone().with_cache(0.001);
+
+        for i in 0..200 {
+            let t = i as Float * 0.0173;
+            let p = Point::new(t.sin(), t * 0.5 - 1.0, t.cos());
+            let a = exact.pattern_at_object(&sphere, p);
+            let b = cached.pattern_at_object(&sphere, p);
+            assert_abs_diff_eq!(a, b, epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn cached_noise_reuses_cells_for_repeated_nearby_samples() {
+        let sphere = Shape::sphere();
+        let cached = Pattern::noise(Color::BLACK, Color::WHITE, 8).with_cache(0.01);
+        let PatternType::Noise {
+            cache: Some(cache), ..
+        } = &cached.pattern_type
+        else {
+            panic!("noise pattern should have a cache");
+        };
+
+        // 20,000 samples along a short segment, spanning only a few cells
+        for i in 0..20_000 {
+            let t = (i % 100) as Float * 0.0005;
+            cached.pattern_at_object(&sphere, Point::new(0.3 + t, 0.2 - t, 0.1 + t));
+        }
+
+        assert!(cache.len() <= 16, "{} cells", cache.len());
+        assert_eq!(cache.hits(), 20_000 - cache.len());
+    }
 }
@@ -2,21 +2,44 @@ use std::ops;
 
 use approx::AbsDiffEq;
 
+use crate::utils::EPSILON;
+use crate::utils::Float;
 use crate::vector::Vector;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Point {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
 }
 
 impl Point {
-    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+    pub const fn new(x: Float, y: Float, z: Float) -> Self {
         Self { x, y, z }
     }
 
     pub const ORIGIN: Point = Point::new(0.0, 0.0, 0.0);
+
+    pub fn midpoint(a: Point, b: Point) -> Point {
+        a + (b - a) * 0.5
+    }
+
+    // the average position of `points`; the origin if there are none
+    pub fn centroid(points: &[Point]) -> Point {
+        if points.is_empty() {
+            return Point::ORIGIN;
+        }
+
+        let sum = points.iter().fold(Vector::new(0.0, 0.0, 0.0), |acc, &p| {
+            acc + (p - Point::ORIGIN)
+        });
+        Point::ORIGIN + sum / points.len() as Float
+    }
+
+    // compare against `other` using a caller-chosen tolerance
+    pub fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        self.abs_diff_eq(other, epsilon)
+    }
 }
 
 impl ops::Add<Vector> for Point {
@@ -27,6 +50,18 @@ impl ops::Add<Vector> for Point {
     }
 }
 
+impl ops::AddAssign<Vector> for Point {
+    fn add_assign(&mut self, other: Vector) {
+        *self = *self + other;
+    }
+}
+
+impl ops::SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, other: Vector) {
+        *self = *self - other;
+    }
+}
+
 impl ops::Sub for Point {
     type Output = Vector;
 
@@ -43,27 +78,27 @@ impl ops::Sub<Vector> for Point {
     }
 }
 
-impl ops::Mul<f64> for Point {
+impl ops::Mul<Float> for Point {
     type Output = Self;
 
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
     }
 }
 
-impl ops::Div<f64> for Point {
+impl ops::Div<Float> for Point {
     type Output = Self;
 
-    fn div(self, other: f64) -> Self {
+    fn div(self, other: Float) -> Self {
         Self::new(self.x / other, self.y / other, self.z / other)
     }
 }
 
 impl AbsDiffEq for Point {
-    type Epsilon = f64;
+    type Epsilon = Float;
 
     fn default_epsilon() -> Self::Epsilon {
-        1e-4
+        EPSILON
     }
 
     fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
@@ -85,6 +120,16 @@ mod tests {
         assert_abs_diff_eq!(Point::new(1.0, 1.0, 6.0), p + v);
     }
 
+    #[test]
+    fn add_assign_and_sub_assign_vector() {
+        let mut p = Point::new(3.0, -2.0, 5.0);
+        p += Vector::new(-2.0, 3.0, 1.0);
+        assert_abs_diff_eq!(p, Point::new(1.0, 1.0, 6.0));
+
+        p -= Vector::new(1.0, 1.0, 1.0);
+        assert_abs_diff_eq!(p, Point::new(0.0, 0.0, 5.0));
+    }
+
     #[test]
     fn sub_point_from_point() {
         let p1 = Point::new(3.0, 2.0, 1.0);
@@ -119,4 +164,36 @@ mod tests {
 
         assert_abs_diff_eq!(a / 2.0, Point::new(0.5, -1.0, 1.5));
     }
+
+    #[test]
+    fn approx_eq_with_custom_epsilon() {
+        let a = Point::new(1.0, 2.0, 3.0);
+        let b = Point::new(1.001, 2.0, 2.999);
+
+        assert!(a.approx_eq(&b, 1e-2));
+        assert!(!a.approx_eq(&b, 1e-6));
+    }
+
+    #[test]
+    fn midpoint_of_two_points() {
+        let m = Point::midpoint(Point::ORIGIN, Point::new(2.0, 2.0, 2.0));
+
+        assert_abs_diff_eq!(m, Point::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn centroid_of_the_unit_cube_corners() {
+        let corners: Vec<Point> = (0..8)
+            .map(|i| {
+                Point::new(
+                    (i & 1) as Float,
+                    (i >> 1 & 1) as Float,
+                    (i >> 2 & 1) as Float,
+                )
+            })
+            .collect();
+
+        assert_abs_diff_eq!(Point::centroid(&corners), Point::new(0.5, 0.5, 0.5));
+        assert_abs_diff_eq!(Point::centroid(&[]), Point::ORIGIN);
+    }
 }
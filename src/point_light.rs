@@ -1,5 +1,9 @@
 use crate::color::Color;
+use crate::light::{Light, LightSource};
 use crate::point::Point;
+use crate::utils::Float;
+use crate::vector::Vector;
+use crate::world::World;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PointLight {
@@ -16,6 +20,20 @@ impl PointLight {
     }
 }
 
+impl LightSource for PointLight {
+    fn intensity_at(&self, point: Point, world: &World) -> Float {
+        world.intensity_at(&Light::Point(*self), point)
+    }
+
+    fn direction_to(&self, point: Point) -> Vector {
+        (self.position - point).normalize()
+    }
+
+    fn color(&self) -> Color {
+        self.intensity
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,6 @@
 use crate::matrix::Transformation;
 use crate::point::Point;
+use crate::utils::Float;
 use crate::vector::Vector;
 
 #[derive(Debug, Clone, Copy)]
@@ -13,16 +14,38 @@ impl Ray {
         Self { origin, direction }
     }
 
-    pub fn position(&self, t: f64) -> Point {
+    pub fn position(&self, t: Float) -> Point {
         self.origin + self.direction * t
     }
 
+    // the same ray with its origin moved `epsilon` along the direction, so a
+    // ray spawned on a surface doesn't immediately re-hit it (or one touching it)
+    pub fn advance(&self, epsilon: Float) -> Self {
+        Self {
+            origin: self.origin + self.direction.normalize() * epsilon,
+            direction: self.direction,
+        }
+    }
+
+    // The ray in the space `m` maps to. The direction is transformed as is, so
+    // scaling changes its length; that keeps t meaning the same point in both
+    // spaces, which is what intersecting in object space relies on.
     pub fn transform(&self, m: Transformation) -> Self {
         Self {
             origin: m * self.origin,
             direction: m * self.direction,
         }
     }
+
+    // as `transform`, with the direction renormalized to unit length, for when
+    // t should be a distance in the new space rather than match the old one
+    pub fn transform_normalized(&self, m: Transformation) -> Self {
+        let r = self.transform(m);
+        Self {
+            origin: r.origin,
+            direction: r.direction.normalize(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -69,4 +92,25 @@ mod tests {
         assert_abs_diff_eq!(transformed_ray.origin, Point::new(2.0, 6.0, 12.0));
         assert_abs_diff_eq!(transformed_ray.direction, Vector::new(0.0, 3.0, 0.0));
     }
+
+    #[test]
+    fn transform_normalized_keeps_directions_unit_length() {
+        let r = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
+        let m = Matrix::scaling(2.0, 3.0, 4.0);
+
+        assert_abs_diff_eq!(r.transform(m).direction.magnitude(), 3.0);
+        let normalized = r.transform_normalized(m);
+        assert_abs_diff_eq!(normalized.direction.magnitude(), 1.0);
+        assert_abs_diff_eq!(normalized.direction, Vector::new(0.0, 1.0, 0.0));
+        assert_abs_diff_eq!(normalized.origin, r.transform(m).origin);
+    }
+
+    #[test]
+    fn advancing_a_ray_moves_its_origin_along_the_direction() {
+        let r = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 0.0, 4.0));
+        let advanced = r.advance(0.5);
+
+        assert_abs_diff_eq!(advanced.origin, Point::new(1.0, 2.0, 3.5));
+        assert_abs_diff_eq!(advanced.direction, r.direction);
+    }
 }
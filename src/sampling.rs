@@ -0,0 +1,418 @@
+use crate::utils::consts::{FRAC_PI_2, PI};
+
+use crate::color::Color;
+use crate::point::Point;
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// Small deterministic random number generator (SplitMix64).
+// Not suitable for anything but sampling.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    // seed from a position, so the same hit point always gets the same samples
+    #[allow(clippy::unnecessary_cast)] // the bits are a u32 under the `f32` feature
+    pub fn from_point(p: Point) -> Self {
+        let mut rng = Self(p.x.to_bits() as u64);
+        rng.0 ^= rng.next_u64() ^ p.y.to_bits() as u64;
+        rng.0 ^= rng.next_u64() ^ p.z.to_bits() as u64;
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // uniformly distributed in [0, 1)
+    pub fn next_float(&mut self) -> Float {
+        unit_float(self.next_u64())
+    }
+}
+
+// the top bits of `u` as a fraction in [0, 1), keeping only as many as
+// `Float` holds exactly so rounding can never reach 1.0
+fn unit_float(u: u64) -> Float {
+    let bits = Float::MANTISSA_DIGITS;
+    (u >> (64 - bits)) as Float / (1u64 << bits) as Float
+}
+
+// How glossy reflection rays are distributed inside the reflection cone
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GlossySampling {
+    // every direction in the cone is equally likely
+    #[default]
+    UniformCone,
+    // directions near the mirror direction are favored (importance sampling)
+    CosineWeighted,
+}
+
+// How anti-aliasing spreads a pixel's samples over its area
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SamplePattern {
+    // one random point in each cell of a near-square grid (stratified jitter)
+    #[default]
+    Grid,
+    // the low-discrepancy Halton sequence in bases 2 and 3, randomly shifted
+    // (wrapping around) so neighboring pixels don't share a pattern
+    Halton,
+    // best-candidate points: each new sample is the farthest of several
+    // random candidates from those already placed
+    BlueNoise,
+}
+
+impl SamplePattern {
+    const BLUE_NOISE_CANDIDATES: usize = 8;
+
+    // `count` sample positions inside a pixel, as offsets in [0, 1) x [0, 1)
+    // from its top left corner
+    pub fn offsets(self, count: usize, rng: &mut Rng) -> Vec<(Float, Float)> {
+        match self {
+            SamplePattern::Grid => {
+                // the most nearly square rows x cols that is exactly `count`
+                let rows = (1..=(count as Float).sqrt() as usize)
+                    .rev()
+                    .find(|r| count.is_multiple_of(*r))
+                    .unwrap_or(1);
+                let cols = count / rows;
+                (0..count)
+                    .map(|i| {
+                        let (col, row) = (i % cols, i / cols);
+                        (
+                            (col as Float + rng.next_float()) / cols as Float,
+                            (row as Float + rng.next_float()) / rows as Float,
+                        )
+                    })
+                    .collect()
+            }
+            SamplePattern::Halton => {
+                let (sx, sy) = (rng.next_float(), rng.next_float());
+                (1..=count)
+                    .map(|i| {
+                        (
+                            (radical_inverse(i, 2) + sx).fract(),
+                            (radical_inverse(i, 3) + sy).fract(),
+                        )
+                    })
+                    .collect()
+            }
+            SamplePattern::BlueNoise => {
+                // distance on the torus, so samples near opposite edges count as close
+                let gap = |a: (Float, Float), b: (Float, Float)| {
+                    let dx = (a.0 - b.0).abs().min(1.0 - (a.0 - b.0).abs());
+                    let dy = (a.1 - b.1).abs().min(1.0 - (a.1 - b.1).abs());
+                    dx * dx + dy * dy
+                };
+                let mut points: Vec<(Float, Float)> = Vec::with_capacity(count);
+                while points.len() < count {
+                    let best = (0..Self::BLUE_NOISE_CANDIDATES)
+                        .map(|_| (rng.next_float(), rng.next_float()))
+                        .map(|c| {
+                            let nearest = points
+                                .iter()
+                                .map(|&p| gap(c, p))
+                                .fold(Float::INFINITY, Float::min);
+                            (c, nearest)
+                        })
+                        .max_by(|a, b| a.1.total_cmp(&b.1))
+                        .unwrap()
+                        .0;
+                    points.push(best);
+                }
+                points
+            }
+        }
+    }
+}
+
+// How anti-aliasing weights a pixel's samples when averaging them
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReconstructionFilter {
+    // every sample counts the same
+    #[default]
+    Box,
+    // weight falls off linearly to 0 one pixel from the center, in x and y
+    Tent,
+    // weight falls off as a Gaussian of the distance from the center, in pixels
+    Gaussian {
+        sigma: Float,
+    },
+}
+
+impl ReconstructionFilter {
+    // weight of a sample (dx, dy) pixels away from the pixel center
+    pub fn weight(self, dx: Float, dy: Float) -> Float {
+        match self {
+            ReconstructionFilter::Box => 1.0,
+            ReconstructionFilter::Tent => (1.0 - dx.abs()).max(0.0) * (1.0 - dy.abs()).max(0.0),
+            ReconstructionFilter::Gaussian { sigma } => {
+                (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+            }
+        }
+    }
+
+    // weighted average of samples taken at `offsets` (as from
+    // `SamplePattern::offsets`) inside the pixel
+    pub fn resolve(self, samples: &[((Float, Float), Color)]) -> Color {
+        let (colors, weights): (Vec<Color>, Vec<Float>) = samples
+            .iter()
+            .map(|&((x, y), c)| (c, self.weight(x - 0.5, y - 0.5)))
+            .unzip();
+        Color::mix_many(&colors, &weights)
+    }
+}
+
+// the digits of `i` in `base`, mirrored about the radix point: the van der
+// Corput sequence 1/2, 1/4, 3/4, ... for base 2
+fn radical_inverse(mut i: usize, base: usize) -> Float {
+    let mut result = 0.0;
+    let mut scale = 1.0 / base as Float;
+    while i > 0 {
+        result += (i % base) as Float * scale;
+        i /= base;
+        scale /= base as Float;
+    }
+    result
+}
+
+// Sample a direction in the cone around `axis` whose half-angle grows with
+// `roughness` (0 = mirror, 1 = hemisphere). Returns the direction and a weight
+// such that the mean of weight * radiance(direction) estimates the
+// cosine-weighted average radiance over the cone for either strategy.
+pub fn sample_glossy(
+    axis: Vector,
+    roughness: Float,
+    sampling: GlossySampling,
+    rng: &mut Rng,
+) -> (Vector, Float) {
+    let half_angle = roughness.clamp(0.0, 1.0) * FRAC_PI_2;
+    let cos_max = half_angle.cos();
+    let sin_max = half_angle.sin();
+    let (u1, u2) = (rng.next_float(), rng.next_float());
+    let phi = 2.0 * PI * u2;
+
+    let (cos_theta, weight) = match sampling {
+        GlossySampling::UniformCone => {
+            let cos_theta = 1.0 - u1 * (1.0 - cos_max);
+            // pdf = 1 / solid angle, rescaled by the cosine lobe's normalization
+            (cos_theta, 2.0 * cos_theta / (1.0 + cos_max))
+        }
+        GlossySampling::CosineWeighted => {
+            // uniform on the disk under the cone projects to a cosine-weighted lobe
+            let r = sin_max * u1.sqrt();
+            ((1.0 - r * r).sqrt(), 1.0)
+        }
+    };
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+    let (t, b) = orthonormal_basis(axis);
+    let dir = t * (sin_theta * phi.cos()) + b * (sin_theta * phi.sin()) + axis * cos_theta;
+
+    (dir, weight)
+}
+
+// a uniformly distributed point in the unit ball, as an offset from its center
+pub fn random_in_unit_sphere(rng: &mut Rng) -> Vector {
+    loop {
+        let v = Vector::new(
+            2.0 * rng.next_float() - 1.0,
+            2.0 * rng.next_float() - 1.0,
+            2.0 * rng.next_float() - 1.0,
+        );
+        if v.dot(v) <= 1.0 {
+            return v;
+        }
+    }
+}
+
+// two unit vectors perpendicular to `axis` and to each other
+pub(crate) fn orthonormal_basis(axis: Vector) -> (Vector, Vector) {
+    let helper = if axis.x.abs() > 0.9 {
+        Vector::new(0.0, 1.0, 0.0)
+    } else {
+        Vector::new(1.0, 0.0, 0.0)
+    };
+    let t = helper.cross(axis).normalize();
+    (t, axis.cross(t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ROUNDING_EPSILON;
+    use approx::assert_abs_diff_eq;
+
+    fn mean_and_variance(samples: &[Float]) -> (Float, Float) {
+        let n = samples.len() as Float;
+        let mean = samples.iter().sum::<Float>() / n;
+        let var = samples.iter().map(|s| (s - mean).powi(2)).sum::<Float>() / n;
+        (mean, var)
+    }
+
+    #[test]
+    fn radical_inverse_mirrors_the_digits() {
+        let base2: Vec<Float> = (1..=4).map(|i| radical_inverse(i, 2)).collect();
+        assert_eq!(base2, [0.5, 0.25, 0.75, 0.125]);
+        assert_abs_diff_eq!(radical_inverse(5, 3), 7.0 / 9.0);
+    }
+
+    #[test]
+    fn sample_patterns_fill_the_pixel() {
+        let mut rng = Rng::new(3);
+        for pattern in [
+            SamplePattern::Grid,
+            SamplePattern::Halton,
+            SamplePattern::BlueNoise,
+        ] {
+            for count in [1, 7, 16] {
+                let offsets = pattern.offsets(count, &mut rng);
+                assert_eq!(offsets.len(), count);
+                for (x, y) in offsets {
+                    assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+                }
+            }
+        }
+
+        // a grid puts exactly one sample in each cell
+        let mut cells: Vec<(usize, usize)> = SamplePattern::Grid
+            .offsets(8, &mut rng)
+            .iter()
+            .map(|&(x, y)| ((x * 4.0) as usize, (y * 2.0) as usize))
+            .collect();
+        cells.sort();
+        cells.dedup();
+        assert_eq!(cells.len(), 8);
+    }
+
+    #[test]
+    fn halton_estimates_edge_coverage_better_than_pure_jitter() {
+        let count = 16;
+        let trials = 500;
+        let mut rng = Rng::new(11);
+        let (mut halton_error, mut jitter_error) = (0.0, 0.0);
+
+        for _ in 0..trials {
+            // a random straight edge crossing the pixel
+            let angle = rng.next_float() * PI;
+            let (nx, ny) = (angle.cos(), angle.sin());
+            let offset = 0.5 * (nx + ny) + 0.8 * (rng.next_float() - 0.5);
+            let inside = |&(x, y): &(Float, Float)| x * nx + y * ny < offset;
+
+            let res = 100;
+            let covered = (0..res * res)
+                .map(|i| ((i % res) as Float + 0.5, (i / res) as Float + 0.5))
+                .filter(|&(x, y)| inside(&(x / res as Float, y / res as Float)))
+                .count();
+            let truth = covered as Float / (res * res) as Float;
+
+            let estimate = |points: Vec<(Float, Float)>| {
+                points.iter().filter(|p| inside(p)).count() as Float / count as Float
+            };
+            let halton = SamplePattern::Halton.offsets(count, &mut rng);
+            let jitter = (0..count)
+                .map(|_| (rng.next_float(), rng.next_float()))
+                .collect();
+            halton_error += (estimate(halton) - truth).powi(2);
+            jitter_error += (estimate(jitter) - truth).powi(2);
+        }
+
+        assert!(
+            halton_error < 0.5 * jitter_error,
+            "halton {halton_error} vs jitter {jitter_error}"
+        );
+    }
+
+    #[test]
+    fn filters_agree_on_a_single_centered_sample() {
+        let sample = [((0.5, 0.5), Color::new(0.2, 0.4, 0.6))];
+        for filter in [
+            ReconstructionFilter::Box,
+            ReconstructionFilter::Tent,
+            ReconstructionFilter::Gaussian { sigma: 0.5 },
+        ] {
+            assert_eq!(filter.resolve(&sample), sample[0].1);
+        }
+    }
+
+    #[test]
+    fn gaussian_favors_the_central_sample() {
+        let samples = [
+            ((0.5, 0.5), Color::RED),
+            ((0.05, 0.05), Color::BLUE),
+            ((0.95, 0.1), Color::BLUE),
+        ];
+        let boxed = ReconstructionFilter::Box.resolve(&samples);
+        let tent = ReconstructionFilter::Tent.resolve(&samples);
+        let gaussian = ReconstructionFilter::Gaussian { sigma: 0.3 }.resolve(&samples);
+
+        assert_abs_diff_eq!(boxed.red, 1.0 / 3.0);
+        assert!(tent.red > boxed.red);
+        assert!(gaussian.red > tent.red);
+        assert_abs_diff_eq!(gaussian.red + gaussian.blue, 1.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn rng_is_deterministic_and_in_range() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..1000 {
+            let x = a.next_float();
+            assert_eq!(x, b.next_float());
+            assert!((0.0..1.0).contains(&x));
+        }
+        assert!(unit_float(u64::MAX) < 1.0);
+    }
+
+    #[test]
+    fn glossy_samples_stay_inside_the_cone() {
+        let axis = Vector::new(0.0, 1.0, 0.0);
+        let cos_max = (0.3 * FRAC_PI_2).cos();
+        let mut rng = Rng::new(7);
+        for sampling in [GlossySampling::UniformCone, GlossySampling::CosineWeighted] {
+            for _ in 0..500 {
+                let (dir, _) = sample_glossy(axis, 0.3, sampling, &mut rng);
+                assert_abs_diff_eq!(dir.magnitude(), 1.0, epsilon = ROUNDING_EPSILON);
+                assert!(dir.dot(axis) >= cos_max - ROUNDING_EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn random_points_fall_inside_the_unit_sphere() {
+        let mut rng = Rng::new(99);
+        for _ in 0..1000 {
+            assert!(random_in_unit_sphere(&mut rng).magnitude() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn cosine_weighted_sampling_has_lower_variance() {
+        // a sky that brightens toward +y, sampled around a tilted reflection
+        let radiance = |d: Vector| 0.5 + 0.5 * d.y;
+        let axis = Vector::new(1.0, 1.0, 0.0).normalize();
+        let estimate = |sampling| {
+            let mut rng = Rng::new(1234);
+            (0..4096)
+                .map(|_| {
+                    let (dir, weight) = sample_glossy(axis, 0.6, sampling, &mut rng);
+                    weight * radiance(dir)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let (uniform_mean, uniform_var) = mean_and_variance(&estimate(GlossySampling::UniformCone));
+        let (cosine_mean, cosine_var) =
+            mean_and_variance(&estimate(GlossySampling::CosineWeighted));
+
+        assert_abs_diff_eq!(uniform_mean, cosine_mean, epsilon = 0.01);
+        assert!(cosine_var < uniform_var);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::utils::consts::PI;
+
+use rayon::prelude::*;
+
+use crate::environment::{equirect_direction, equirect_map};
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::utils::Float;
+use crate::world::World;
+
+// How `World` decides whether a point can see a light
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ShadowMode {
+    // cast a shadow ray per query (exact)
+    #[default]
+    RayTraced,
+    // look up a depth map rendered once from each light's position, with
+    // `resolution` x `resolution` texels (fast, approximate). Area lights are
+    // treated as a point at their center, so their shadows are hard.
+    ShadowMap {
+        resolution: usize,
+    },
+}
+
+// Distance to the nearest surface in every direction around a point,
+// stored as a latitude/longitude image (see `equirect_map`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowMap {
+    pub origin: Point,
+    resolution: usize,
+    depth: Vec<Float>,
+}
+
+impl ShadowMap {
+    // `resolution` is at least 1, as an empty map has nothing to look up
+    pub fn render(world: &World, origin: Point, resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        let depth = (0..resolution * resolution)
+            .into_par_iter()
+            .map(|i| Self::texel_depth(world, origin, resolution, i))
+            .collect();
+
+        Self {
+            origin,
+            resolution,
+            depth,
+        }
+    }
+
+    // as `render`, on the calling thread only; safe to call from inside a
+    // parallel render, where nesting rayon work could re-enter the caller
+    pub fn render_sequential(world: &World, origin: Point, resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        let depth = (0..resolution * resolution)
+            .map(|i| Self::texel_depth(world, origin, resolution, i))
+            .collect();
+
+        Self {
+            origin,
+            resolution,
+            depth,
+        }
+    }
+
+    // distance from `origin` to the nearest surface through the center of texel `i`
+    fn texel_depth(world: &World, origin: Point, resolution: usize, i: usize) -> Float {
+        let u = ((i % resolution) as Float + 0.5) / resolution as Float;
+        let v = 1.0 - ((i / resolution) as Float + 0.5) / resolution as Float;
+        let ray = Ray::new(origin, equirect_direction(u, v));
+        world.hit_distance(ray).unwrap_or(Float::INFINITY)
+    }
+
+    // is something closer to the origin than `point`, in its direction?
+    pub fn is_shadowed(&self, point: Point) -> bool {
+        let to_point = point - self.origin;
+        let distance = to_point.magnitude();
+        let (u, v) = equirect_map(to_point);
+
+        // interpolate between the four nearest texel centers, so the depth of a
+        // smooth surface is tracked between samples rather than stepping
+        let res = self.resolution as isize;
+        let x = u * res as Float - 0.5;
+        let y = (1.0 - v) * res as Float - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let depth_at = |dx: isize, dy: isize| {
+            let x = (x0 as isize + dx).rem_euclid(res);
+            let y = (y0 as isize + dy).clamp(0, res - 1);
+            self.depth[(x + y * res) as usize]
+        };
+        let top = depth_at(0, 0) * (1.0 - tx) + depth_at(1, 0) * tx;
+        let bottom = depth_at(0, 1) * (1.0 - tx) + depth_at(1, 1) * tx;
+        let depth = top * (1.0 - ty) + bottom * ty;
+
+        // a little slack, growing with distance like a texel does, keeps
+        // surfaces from shadowing themselves ("shadow acne")
+        let bias = distance * PI / res as Float;
+        distance > depth + bias
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::matrix::Transformation;
+    use crate::point_light::PointLight;
+    use crate::shapes::Shape;
+
+    #[test]
+    fn points_behind_a_blocker_are_shadowed() {
+        let light = Point::new(0.0, 10.0, 0.0);
+        let world = World::new(vec![Shape::sphere()], PointLight::new(light, Color::WHITE));
+        let map = ShadowMap::render(&world, light, 128);
+
+        assert!(map.is_shadowed(Point::new(0.0, -5.0, 0.0)));
+        assert!(!map.is_shadowed(Point::new(0.0, 5.0, 0.0)));
+        assert!(!map.is_shadowed(Point::new(5.0, -5.0, 0.0)));
+    }
+
+    #[test]
+    fn sequential_render_matches_parallel_render() {
+        let light = Point::new(0.0, 10.0, 0.0);
+        let world = World::new(vec![Shape::sphere()], PointLight::new(light, Color::WHITE));
+
+        assert_eq!(
+            ShadowMap::render_sequential(&world, light, 32),
+            ShadowMap::render(&world, light, 32)
+        );
+    }
+
+    #[test]
+    fn empty_directions_are_never_shadowed() {
+        let world = World::new(
+            vec![Shape::sphere().with_transform(Transformation::translation(5.0, 0.0, 0.0))],
+            PointLight::new(Point::ORIGIN, Color::WHITE),
+        );
+        let map = ShadowMap::render(&world, Point::ORIGIN, 64);
+
+        assert!(!map.is_shadowed(Point::new(-100.0, 0.0, 0.0)));
+        assert!(map.is_shadowed(Point::new(100.0, 0.0, 0.0)));
+    }
+}
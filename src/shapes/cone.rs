@@ -0,0 +1,254 @@
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shapes::cylinder::check_cap;
+use crate::utils::EPSILON;
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// A double cone around the y axis, with radius |y|, truncated to
+// minimum < y < maximum and optionally capped at both ends
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+    pub minimum: Float,
+    pub maximum: Float,
+    pub closed: bool,
+    // false leaves only the caps (if closed), e.g. for discs and rings
+    pub tube: bool,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cone {
+    // infinitely long and open at both ends
+    pub fn new() -> Self {
+        Self {
+            minimum: Float::NEG_INFINITY,
+            maximum: Float::INFINITY,
+            closed: false,
+            tube: true,
+        }
+    }
+
+    pub fn with_bounds(mut self, minimum: Float, maximum: Float) -> Self {
+        self.minimum = minimum;
+        self.maximum = maximum;
+        self
+    }
+
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    pub fn with_tube(mut self, tube: bool) -> Self {
+        self.tube = tube;
+        self
+    }
+
+    // Compute the intersections of a ray and a Cone, sorted by t
+    // Assumes `ray` is already in object space
+    pub fn local_intersect(&self, ray: Ray) -> Vec<Float> {
+        let mut xs = Vec::new();
+
+        // bounds that are equal leave no sides at all, just a disc if closed;
+        // inverted bounds leave nothing
+        if self.minimum > self.maximum {
+            return xs;
+        }
+
+        if self.tube && self.minimum < self.maximum {
+            let (o, d) = (ray.origin, ray.direction);
+            let a = d.x.powi(2) - d.y.powi(2) + d.z.powi(2);
+            let b = 2.0 * (o.x * d.x - o.y * d.y + o.z * d.z);
+            let c = o.x.powi(2) - o.y.powi(2) + o.z.powi(2);
+
+            let mut side = Vec::new();
+            if a.abs() < EPSILON {
+                // parallel to one of the halves: a single hit, if any
+                if b.abs() >= EPSILON {
+                    side.push(-c / (2.0 * b));
+                }
+            } else {
+                let discriminant = b * b - 4.0 * a * c;
+                // a ray grazing the surface may round to just below zero
+                if discriminant >= -EPSILON {
+                    let sqrt_disc = discriminant.max(0.0).sqrt();
+                    side.push((-b - sqrt_disc) / (2.0 * a));
+                    side.push((-b + sqrt_disc) / (2.0 * a));
+                }
+            }
+
+            xs.extend(side.into_iter().filter(|&t| {
+                let y = o.y + t * d.y;
+                self.minimum < y && y < self.maximum
+            }));
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        xs.sort_by(|a, b| a.total_cmp(b));
+        xs
+    }
+
+    fn intersect_caps(&self, ray: Ray, xs: &mut Vec<Float>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        let mut caps = vec![self.minimum];
+        if self.maximum != self.minimum {
+            caps.push(self.maximum);
+        }
+        for y in caps {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, y.abs()) {
+                xs.push(t);
+            }
+        }
+    }
+
+    // Object-space normal
+    pub fn local_normal_at(&self, point: Point) -> Vector {
+        let dist = point.x.powi(2) + point.z.powi(2);
+
+        if dist < self.maximum.powi(2) && point.y >= self.maximum - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else if dist < self.minimum.powi(2) && point.y <= self.minimum + EPSILON {
+            Vector::new(0.0, -1.0, 0.0)
+        } else {
+            let y = dist.sqrt();
+            let y = if point.y > 0.0 { -y } else { y };
+            Vector::new(point.x, y, point.z)
+        }
+    }
+
+    // caps alone enclose nothing
+    pub fn local_contains_point(&self, point: Point) -> bool {
+        self.tube
+            && point.x.powi(2) + point.z.powi(2) < point.y.powi(2)
+            && self.minimum < point.y
+            && point.y < self.maximum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray() {
+        let cone = Cone::new();
+        for (origin, direction, t0, t1) in [
+            (
+                Point::new(0.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                5.0,
+                5.0,
+            ),
+            (
+                Point::new(0.0, 0.0, -5.0),
+                Vector::new(1.0, 1.0, 1.0),
+                8.66025,
+                8.66025,
+            ),
+            (
+                Point::new(1.0, 1.0, -5.0),
+                Vector::new(-0.5, -1.0, 1.0),
+                4.55006,
+                49.44994,
+            ),
+        ] {
+            let xs = cone.local_intersect(Ray::new(origin, direction.normalize()));
+            assert_eq!(xs.len(), 2);
+            assert_abs_diff_eq!(xs[0], t0, epsilon = 1e-4);
+            assert_abs_diff_eq!(xs[1], t1, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn ray_parallel_to_one_half_of_a_cone() {
+        let cone = Cone::new();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 1.0, 1.0).normalize(),
+        );
+        let xs = cone.local_intersect(r);
+        assert_eq!(xs.len(), 1);
+        assert_abs_diff_eq!(xs[0], 0.35355, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn intersecting_the_caps_of_a_closed_cone() {
+        let cone = Cone::new().with_bounds(-0.5, 0.5).with_closed(true);
+        for (origin, direction, count) in [
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0), 0),
+            (Point::new(0.0, 0.0, -0.25), Vector::new(0.0, 1.0, 1.0), 2),
+            (Point::new(0.0, 0.0, -0.25), Vector::new(0.0, 1.0, 0.0), 4),
+        ] {
+            let xs = cone.local_intersect(Ray::new(origin, direction.normalize()));
+            assert_eq!(xs.len(), count);
+        }
+    }
+
+    #[test]
+    fn normal_on_a_cone() {
+        let cone = Cone::new();
+        assert_eq!(
+            cone.local_normal_at(Point::ORIGIN),
+            Vector::new(0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            cone.local_normal_at(Point::new(1.0, 1.0, 1.0)),
+            Vector::new(1.0, -Float::sqrt(2.0), 1.0)
+        );
+        assert_eq!(
+            cone.local_normal_at(Point::new(-1.0, -1.0, 0.0)),
+            Vector::new(-1.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn equal_bounds_leave_no_sides() {
+        let open = Cone::new().with_bounds(1.0, 1.0);
+        let down = Ray::new(Point::new(0.5, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let across = Ray::new(Point::new(-5.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(open.local_intersect(down).is_empty());
+        assert!(open.local_intersect(across).is_empty());
+
+        // closed, it is a single disc of radius 1 at y = 1
+        let disc = open.with_closed(true);
+        assert_eq!(disc.local_intersect(down), vec![2.0]);
+        assert!(disc.local_intersect(across).is_empty());
+    }
+
+    #[test]
+    fn inverted_bounds_are_empty() {
+        let cone = Cone::new().with_bounds(1.0, -1.0).with_closed(true);
+        for r in [
+            Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 0.0, -0.25), Vector::new(0.0, 1.0, 0.0)),
+            Ray::new(Point::new(0.5, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+        ] {
+            assert!(cone.local_intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn a_cone_without_its_sides_is_just_caps() {
+        let caps = Cone::new()
+            .with_bounds(-0.5, 0.5)
+            .with_closed(true)
+            .with_tube(false);
+
+        // the full cone meets sides and caps four times along this ray
+        let up = Ray::new(Point::new(0.0, 0.0, -0.25), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(caps.local_intersect(up), vec![-0.5, 0.5]);
+        let across = Ray::new(Point::new(-5.0, 0.25, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(caps.local_intersect(across).is_empty());
+        assert!(!caps.local_contains_point(Point::new(0.0, 0.25, 0.0)));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::utils::EPSILON;
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// A radius 1 cylinder around the y axis, truncated to minimum < y < maximum
+// and optionally capped at both ends. Size and place it with the shape's
+// transform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cylinder {
+    pub minimum: Float,
+    pub maximum: Float,
+    pub closed: bool,
+    // false leaves only the caps (if closed), e.g. for discs and rings
+    pub tube: bool,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cylinder {
+    // infinitely long and open at both ends
+    pub fn new() -> Self {
+        Self {
+            minimum: Float::NEG_INFINITY,
+            maximum: Float::INFINITY,
+            closed: false,
+            tube: true,
+        }
+    }
+
+    pub fn with_bounds(mut self, minimum: Float, maximum: Float) -> Self {
+        self.minimum = minimum;
+        self.maximum = maximum;
+        self
+    }
+
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    pub fn with_tube(mut self, tube: bool) -> Self {
+        self.tube = tube;
+        self
+    }
+
+    // Compute the intersections of a ray and a Cylinder, sorted by t
+    // Assumes `ray` is already in object space
+    pub fn local_intersect(&self, ray: Ray) -> Vec<Float> {
+        let mut xs = Vec::new();
+
+        // bounds that are equal leave no tube at all, just a disc if closed;
+        // inverted bounds leave nothing
+        if self.minimum > self.maximum {
+            return xs;
+        }
+
+        let a = ray.direction.x.powi(2) + ray.direction.z.powi(2);
+        // parallel to the y axis, the ray can only hit the caps
+        if self.tube && self.minimum < self.maximum && a.abs() >= EPSILON {
+            let b = 2.0 * ray.origin.x * ray.direction.x + 2.0 * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x.powi(2) + ray.origin.z.powi(2) - 1.0;
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return xs;
+            }
+
+            let sqrt_disc = discriminant.sqrt();
+            for t in [(-b - sqrt_disc) / (2.0 * a), (-b + sqrt_disc) / (2.0 * a)] {
+                let y = ray.origin.y + t * ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(t);
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        xs.sort_by(|a, b| a.total_cmp(b));
+        xs
+    }
+
+    fn intersect_caps(&self, ray: Ray, xs: &mut Vec<Float>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        let mut caps = vec![self.minimum];
+        if self.maximum != self.minimum {
+            caps.push(self.maximum);
+        }
+        for y in caps {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, 1.0) {
+                xs.push(t);
+            }
+        }
+    }
+
+    // Object-space normal
+    pub fn local_normal_at(&self, point: Point) -> Vector {
+        let dist = point.x.powi(2) + point.z.powi(2);
+
+        if dist < 1.0 && point.y >= self.maximum - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && point.y <= self.minimum + EPSILON {
+            Vector::new(0.0, -1.0, 0.0)
+        } else {
+            Vector::new(point.x, 0.0, point.z)
+        }
+    }
+
+    // caps alone enclose nothing
+    pub fn local_contains_point(&self, point: Point) -> bool {
+        self.tube
+            && point.x.powi(2) + point.z.powi(2) < 1.0
+            && self.minimum < point.y
+            && point.y < self.maximum
+    }
+}
+
+// is the point at t within `radius` of the y axis?
+pub(crate) fn check_cap(ray: Ray, t: Float, radius: Float) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+    // with a little slack, so rays through the rim aren't lost to rounding
+    x.powi(2) + z.powi(2) <= radius.powi(2) + EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn ray_misses_a_cylinder() {
+        let cyl = Cylinder::new();
+        for (origin, direction) in [
+            (Point::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(1.0, 1.0, 1.0)),
+        ] {
+            let r = Ray::new(origin, direction.normalize());
+            assert!(cyl.local_intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_cylinder() {
+        let cyl = Cylinder::new();
+        for (origin, direction, t0, t1) in [
+            (
+                Point::new(1.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                5.0,
+                5.0,
+            ),
+            (
+                Point::new(0.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, 0.0, -5.0),
+                Vector::new(0.1, 1.0, 1.0),
+                6.80798,
+                7.08872,
+            ),
+        ] {
+            let xs = cyl.local_intersect(Ray::new(origin, direction.normalize()));
+            assert_eq!(xs.len(), 2);
+            assert_abs_diff_eq!(xs[0], t0, epsilon = EPSILON);
+            assert_abs_diff_eq!(xs[1], t1, epsilon = EPSILON);
+        }
+    }
+
+    #[test]
+    fn normal_on_a_cylinder() {
+        let cyl = Cylinder::new();
+        assert_eq!(
+            cyl.local_normal_at(Point::new(1.0, 0.0, 0.0)),
+            Vector::new(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            cyl.local_normal_at(Point::new(0.0, 5.0, -1.0)),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+        assert_eq!(
+            cyl.local_normal_at(Point::new(-1.0, 1.0, 0.0)),
+            Vector::new(-1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn intersecting_a_constrained_cylinder() {
+        let cyl = Cylinder::new().with_bounds(1.0, 2.0);
+        for (origin, direction, count) in [
+            (Point::new(0.0, 1.5, 0.0), Vector::new(0.1, 1.0, 0.0), 0),
+            (Point::new(0.0, 3.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.5, -2.0), Vector::new(0.0, 0.0, 1.0), 2),
+        ] {
+            let xs = cyl.local_intersect(Ray::new(origin, direction.normalize()));
+            assert_eq!(xs.len(), count);
+        }
+    }
+
+    #[test]
+    fn intersecting_the_caps_of_a_closed_cylinder() {
+        let cyl = Cylinder::new().with_bounds(1.0, 2.0).with_closed(true);
+        for (origin, direction, count) in [
+            (Point::new(0.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0), 2),
+            (Point::new(0.0, 3.0, -2.0), Vector::new(0.0, -1.0, 2.0), 2),
+            (Point::new(0.0, 4.0, -2.0), Vector::new(0.0, -1.0, 1.0), 2),
+            (Point::new(0.0, 0.0, -2.0), Vector::new(0.0, 1.0, 2.0), 2),
+            (Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 1.0), 2),
+        ] {
+            let xs = cyl.local_intersect(Ray::new(origin, direction.normalize()));
+            assert_eq!(xs.len(), count);
+        }
+    }
+
+    #[test]
+    fn normal_on_the_caps_of_a_closed_cylinder() {
+        let cyl = Cylinder::new().with_bounds(1.0, 2.0).with_closed(true);
+        let down = Vector::new(0.0, -1.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(cyl.local_normal_at(Point::new(0.5, 1.0, 0.0)), down);
+        assert_eq!(cyl.local_normal_at(Point::new(0.0, 1.0, 0.5)), down);
+        assert_eq!(cyl.local_normal_at(Point::new(0.5, 2.0, 0.0)), up);
+        assert_eq!(cyl.local_normal_at(Point::new(0.0, 2.0, 0.5)), up);
+    }
+
+    #[test]
+    fn equal_bounds_leave_no_tube() {
+        let open = Cylinder::new().with_bounds(1.0, 1.0);
+        let side_on = Ray::new(Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let slanted = Ray::new(
+            Point::new(0.0, 3.0, -2.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        assert!(open.local_intersect(side_on).is_empty());
+        assert!(open.local_intersect(slanted).is_empty());
+
+        // closed, it is a single disc at y = 1
+        let disc = open.with_closed(true);
+        assert!(disc.local_intersect(side_on).is_empty());
+        let xs = disc.local_intersect(slanted);
+        assert_eq!(xs.len(), 1);
+        assert_abs_diff_eq!(xs[0], Float::sqrt(2.0) * 2.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn inverted_bounds_are_empty() {
+        let cyl = Cylinder::new().with_bounds(2.0, 1.0).with_closed(true);
+        for (origin, direction) in [
+            (Point::new(0.0, 1.5, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(0.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.0, 3.0, -2.0), Vector::new(0.0, -1.0, 1.0)),
+        ] {
+            let xs = cyl.local_intersect(Ray::new(origin, direction.normalize()));
+            assert!(xs.is_empty());
+        }
+        assert!(!cyl.local_contains_point(Point::new(0.0, 1.5, 0.0)));
+    }
+
+    #[test]
+    fn a_cylinder_without_its_tube_is_just_caps() {
+        let caps = Cylinder::new()
+            .with_bounds(1.0, 2.0)
+            .with_closed(true)
+            .with_tube(false);
+
+        // straight through the side, and slanted through the side and a cap
+        let side_on = Ray::new(Point::new(0.0, 1.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(caps.local_intersect(side_on).is_empty());
+        let slanted = Ray::new(
+            Point::new(0.0, 3.0, -2.0),
+            Vector::new(0.0, -1.0, 2.0).normalize(),
+        );
+        let xs = caps.local_intersect(slanted);
+        assert_eq!(xs.len(), 1);
+        let hit = slanted.position(xs[0]);
+        assert_abs_diff_eq!(hit.y, 2.0, epsilon = 1e-9);
+        assert_eq!(caps.local_normal_at(hit), Vector::new(0.0, 1.0, 0.0));
+
+        // down the axis it meets both caps, and nothing else
+        let down = Ray::new(Point::new(0.5, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(caps.local_intersect(down), vec![1.0, 2.0]);
+
+        assert!(!caps.local_contains_point(Point::new(0.0, 1.5, 0.0)));
+        assert!(
+            caps.clone()
+                .with_closed(false)
+                .local_intersect(down)
+                .is_empty()
+        );
+    }
+}
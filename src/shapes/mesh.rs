@@ -0,0 +1,197 @@
+use crate::bounds::Bounds;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shapes::triangle::Triangle;
+use crate::utils::EPSILON;
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// A triangle mesh intersected as a single shape.
+// A bounding sphere around all the vertices lets most rays skip the triangles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    pub triangles: Vec<Triangle>,
+    pub center: Point,
+    pub radius: Float,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let points = || triangles.iter().flat_map(|t| [t.p1, t.p2, t.p3]);
+
+        // center the sphere on the vertices' bounding box
+        let center =
+            Bounds::from_points(points()).map_or(Point::ORIGIN, |b| Point::midpoint(b.min, b.max));
+        let radius = points()
+            .map(|p| (p - center).magnitude())
+            .fold(0.0, Float::max);
+
+        Self {
+            triangles,
+            center,
+            radius,
+        }
+    }
+
+    // Intersect every triangle the ray could reach, sorted by t, along with
+    // the index of the triangle each hit lies on
+    // Assumes `ray` is already in object space
+    pub fn local_intersect(&self, ray: Ray) -> Vec<(Float, usize)> {
+        self.intersect_counting(ray, &mut 0)
+    }
+
+    // Object-space normal of the hit triangle, or of the triangle the point
+    // lies on when the hit didn't record one
+    pub fn local_normal_at(&self, point: Point, triangle: Option<usize>) -> Vector {
+        self.hit_triangle(point, triangle)
+            .map_or(Vector::new(0.0, 1.0, 0.0), |t| t.local_normal_at(point))
+    }
+
+    // Object-space face normal of that triangle, ignoring vertex normals
+    pub fn local_face_normal_at(&self, point: Point, triangle: Option<usize>) -> Vector {
+        self.hit_triangle(point, triangle)
+            .map_or(Vector::new(0.0, 1.0, 0.0), |t| t.normal)
+    }
+
+    // inside if a ray from the point crosses the surface an odd number of times
+    pub fn local_contains_point(&self, point: Point) -> bool {
+        let ray = Ray::new(point, Vector::new(1.0, 0.0, 0.0));
+        let crossings = self
+            .local_intersect(ray)
+            .into_iter()
+            .filter(|&(t, _)| t > 0.0)
+            .count();
+        crossings % 2 == 1
+    }
+
+    // as `local_intersect`, adding the number of ray-triangle tests to `tested`
+    fn intersect_counting(&self, ray: Ray, tested: &mut usize) -> Vec<(Float, usize)> {
+        if !self.bounding_sphere_hit(ray) {
+            return Vec::new();
+        }
+
+        let mut hits = Vec::new();
+        for (i, t) in self.triangles.iter().enumerate() {
+            *tested += 1;
+            hits.extend(t.local_intersect(ray).iter().map(|t| (t, i)));
+        }
+        hits.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        hits
+    }
+
+    // does the infinite line of the ray pass through the bounding sphere?
+    // (hits behind the origin still count, so results match testing every triangle)
+    fn bounding_sphere_hit(&self, ray: Ray) -> bool {
+        let center_to_ray = ray.origin - self.center;
+        let radius = self.radius + EPSILON;
+        let a = ray.direction.dot(ray.direction);
+        let b = 2.0 * ray.direction.dot(center_to_ray);
+        let c = center_to_ray.dot(center_to_ray) - radius * radius;
+
+        b * b - 4.0 * a * c >= 0.0
+    }
+
+    // the recorded hit triangle; without one, search for the triangle the point lies on
+    fn hit_triangle(&self, point: Point, triangle: Option<usize>) -> Option<&Triangle> {
+        match triangle {
+            Some(i) => self.triangles.get(i),
+            None => self.nearest_triangle(point),
+        }
+    }
+
+    fn nearest_triangle(&self, point: Point) -> Option<&Triangle> {
+        let distance = |t: &Triangle| {
+            let (u, v) = t.barycentric(point);
+            let inside = u >= -EPSILON && v >= -EPSILON && u + v <= 1.0 + EPSILON;
+            let off_plane = (point - t.p1).dot(t.normal).abs();
+            // prefer triangles that contain the point, then the closest plane
+            (!inside, off_plane)
+        };
+
+        self.triangles.iter().min_by(|a, b| {
+            let (a, b) = (distance(a), distance(b));
+            a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obj_file::ParsedObj;
+    use crate::shapes::Shape;
+    use approx::assert_abs_diff_eq;
+
+    // a closed octahedron with outward winding
+    fn octahedron() -> Mesh {
+        let obj = ParsedObj::parse(
+            "v 1 0 0\nv -1 0 0\nv 0 1 0\nv 0 -1 0\nv 0 0 1\nv 0 0 -1\n\
+             f 1 5 3\nf 3 5 2\nf 2 5 4\nf 4 5 1\n\
+             f 3 6 1\nf 2 6 3\nf 4 6 2\nf 1 6 4",
+        );
+        Mesh::new(obj.triangles())
+    }
+
+    #[test]
+    fn bounding_sphere_encloses_the_vertices() {
+        let mesh = octahedron();
+
+        assert_abs_diff_eq!(mesh.center, Point::ORIGIN);
+        assert_abs_diff_eq!(mesh.radius, 1.0);
+    }
+
+    #[test]
+    fn ray_through_the_mesh_hits_entry_and_exit() {
+        let mesh = octahedron();
+        let r = Ray::new(Point::new(0.1, 0.1, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = mesh.local_intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert_abs_diff_eq!(xs[0].0, 4.2, epsilon = 1e-9);
+        assert_abs_diff_eq!(xs[1].0, 5.8, epsilon = 1e-9);
+        let (t, triangle) = xs[0];
+        let expected = Vector::new(1.0, 1.0, -1.0).normalize();
+        assert_abs_diff_eq!(
+            mesh.local_normal_at(r.position(t), Some(triangle)),
+            expected
+        );
+        assert_abs_diff_eq!(mesh.local_normal_at(r.position(t), None), expected);
+    }
+
+    #[test]
+    fn ray_missing_the_bounding_sphere_tests_no_triangles() {
+        let mesh = octahedron();
+        let mut tested = 0;
+
+        let miss = Ray::new(Point::new(0.0, 3.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(mesh.intersect_counting(miss, &mut tested).is_empty());
+        assert_eq!(tested, 0);
+
+        let hit = Ray::new(Point::new(0.1, 0.1, -5.0), Vector::new(0.0, 0.0, 1.0));
+        mesh.intersect_counting(hit, &mut tested);
+        assert_eq!(tested, mesh.triangles.len());
+    }
+
+    #[test]
+    fn intersections_record_the_triangle_hit() {
+        let mesh = octahedron();
+        let shape = Shape::from(mesh.clone());
+        let r = Ray::new(Point::new(0.1, 0.1, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = shape.intersect(r);
+
+        // entering through the +x+y-z face, leaving through +x+y+z
+        assert_eq!(xs.all()[0].triangle, Some(4));
+        assert_eq!(xs.all()[1].triangle, Some(0));
+        let comps = xs.all()[0].prepare_computations(r, &xs);
+        assert_abs_diff_eq!(comps.normal_vector, mesh.triangles[4].normal);
+        assert_abs_diff_eq!(comps.geometric_normal, mesh.triangles[4].normal);
+    }
+
+    #[test]
+    fn mesh_contains_points_inside_its_surface() {
+        let mesh = octahedron();
+
+        assert!(mesh.local_contains_point(Point::new(0.1, 0.2, 0.1)));
+        assert!(!mesh.local_contains_point(Point::new(0.9, 0.9, 0.0)));
+    }
+}
@@ -1,7 +1,19 @@
+pub use cone::*;
+pub use cylinder::*;
+pub use mesh::*;
 pub use plane::*;
+pub use quad::*;
+pub use sdf::*;
 pub use shape::*;
 pub use sphere::*;
+pub use triangle::*;
 
+mod cone;
+mod cylinder;
+mod mesh;
 mod plane;
+mod quad;
+mod sdf;
 mod shape;
 mod sphere;
+mod triangle;
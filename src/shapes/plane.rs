@@ -2,11 +2,19 @@ use crate::intersection::LocalHits;
 use crate::point::Point;
 use crate::ray::Ray;
 use crate::utils::EPSILON;
+use crate::utils::Float;
 use crate::vector::Vector;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Plane;
 
+// Which face of a plane a ray arrives at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Top,    // from +y, against the normal
+    Bottom, // from -y
+}
+
 impl Default for Plane {
     fn default() -> Self {
         Plane
@@ -21,18 +29,38 @@ impl Plane {
     // Compute the intersection of a ray and a Plane
     // Assumes `ray` is already in object space
     pub fn local_intersect(&self, ray: Ray) -> LocalHits {
-        if ray.direction.y.abs() < EPSILON {
-            LocalHits::None // parallel
-        } else {
-            let t = -ray.origin.y / ray.direction.y;
-            LocalHits::One(t)
+        match self.local_intersect_sided(ray) {
+            Some((t, _)) => LocalHits::One(t),
+            None => LocalHits::None,
+        }
+    }
+
+    // like `local_intersect`, also reporting which face the ray hits
+    pub fn local_intersect_sided(&self, ray: Ray) -> Option<(Float, Side)> {
+        // compare the angle rather than the raw component, which shrinks
+        // or grows with the scale of the plane's transform
+        if ray.direction.y.abs() < EPSILON * ray.direction.magnitude() {
+            return None; // parallel
         }
+
+        let t = -ray.origin.y / ray.direction.y;
+        let side = if ray.direction.y < 0.0 {
+            Side::Top
+        } else {
+            Side::Bottom
+        };
+        Some((t, side))
     }
 
     // Object-space normal
     pub fn local_normal_at(&self, _point: Point) -> Vector {
         Vector::new(0.0, 1.0, 0.0) // Normal for a plane is always (0, 1, 0)
     }
+
+    // A plane has no interior
+    pub fn local_contains_point(&self, _point: Point) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -79,4 +107,38 @@ mod tests {
         let r = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
         assert_eq!(p.local_intersect(r), LocalHits::One(1.0));
     }
+
+    #[test]
+    fn shallow_ray_intersects_a_hugely_scaled_plane() {
+        use crate::matrix::Transformation;
+        use crate::shapes::Shape;
+        use crate::utils::ROUNDING_EPSILON;
+
+        let p = Shape::from(Plane::new())
+            .with_transform(Transformation::scaling(1000.0, 1000.0, 1000.0));
+        // in object space the direction's y is a hundredth of the fixed epsilon
+        let slope = 10.0 * EPSILON;
+        let r = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -slope, 1.0));
+        let xs = p.intersect(r);
+
+        assert_eq!(xs.all().len(), 1);
+        assert_abs_diff_eq!(
+            xs.all()[0].t,
+            1.0 / slope,
+            epsilon = ROUNDING_EPSILON / slope
+        );
+    }
+
+    #[test]
+    fn intersection_reports_the_face_hit() {
+        let p = Plane::new();
+        let from_above = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let from_below = Ray::new(Point::new(0.0, -2.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(p.local_intersect_sided(from_above), Some((1.0, Side::Top)));
+        assert_eq!(
+            p.local_intersect_sided(from_below),
+            Some((2.0, Side::Bottom))
+        );
+    }
 }
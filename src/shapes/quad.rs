@@ -0,0 +1,67 @@
+use crate::intersection::LocalHits;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shapes::plane::{Plane, Side};
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// A plane clipped to the square -1 <= x, z <= 1 in object space.
+// Size and place it with the shape's transform.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Quad;
+
+impl Quad {
+    pub fn new() -> Self {
+        Quad
+    }
+
+    // Compute the intersection of a ray and a Quad
+    // Assumes `ray` is already in object space
+    pub fn local_intersect(&self, ray: Ray) -> LocalHits {
+        match self.local_intersect_sided(ray) {
+            Some((t, _)) => LocalHits::One(t),
+            None => LocalHits::None,
+        }
+    }
+
+    // like `local_intersect`, also reporting which face the ray hits
+    pub fn local_intersect_sided(&self, ray: Ray) -> Option<(Float, Side)> {
+        Plane::new().local_intersect_sided(ray).filter(|&(t, _)| {
+            let p = ray.position(t);
+            p.x.abs() <= 1.0 && p.z.abs() <= 1.0
+        })
+    }
+
+    // Object-space normal
+    pub fn local_normal_at(&self, _point: Point) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+    }
+
+    // A quad has no interior
+    pub fn local_contains_point(&self, _point: Point) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_inside_the_square_hits_the_quad() {
+        let q = Quad::new();
+        let r = Ray::new(Point::new(0.5, 1.0, -0.5), Vector::new(0.0, -1.0, 0.0));
+        let xs: Vec<Float> = q.local_intersect(r).iter().collect();
+
+        assert_eq!(xs, vec![1.0]);
+    }
+
+    #[test]
+    fn ray_outside_the_square_misses_the_quad() {
+        let q = Quad::new();
+        for origin in [Point::new(1.5, 1.0, 0.0), Point::new(0.0, 1.0, -1.01)] {
+            let r = Ray::new(origin, Vector::new(0.0, -1.0, 0.0));
+            assert_eq!(q.local_intersect(r).iter().count(), 0);
+        }
+    }
+}
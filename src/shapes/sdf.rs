@@ -0,0 +1,170 @@
+use crate::intersection::LocalHits;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::utils::Float;
+use crate::vector::Vector;
+
+// An implicit surface described by a signed distance function: negative inside,
+// positive outside, and never larger than the true distance to the surface.
+#[allow(unpredictable_function_pointer_comparisons)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sdf {
+    pub distance: fn(Point) -> Float,
+}
+
+impl Sdf {
+    const HIT_EPSILON: Float = 1e-7; // close enough to count as on the surface
+    const MIN_TRAVEL: Float = 1e-5; // ignore the surface the march starts on
+    const MAX_DISTANCE: Float = 1e3; // give up once this far along the ray
+    const MAX_STEPS: usize = 1024;
+    const NORMAL_DELTA: Float = 1e-5; // finite difference step for the gradient
+
+    pub fn new(distance: fn(Point) -> Float) -> Self {
+        Self { distance }
+    }
+
+    // Sphere-trace the ray to find where it enters and leaves the surface
+    // Assumes `ray` is already in object space
+    pub fn local_intersect(&self, ray: Ray) -> LocalHits {
+        // march in unit steps, then convert back to the ray's parameterization
+        let speed = ray.direction.magnitude();
+        let dir = ray.direction / speed;
+
+        if (self.distance)(ray.origin) >= 0.0 {
+            let Some(t_in) = self.march(ray.origin, dir, 1.0) else {
+                return LocalHits::None;
+            };
+            match self.march(ray.origin + dir * t_in, dir, -1.0) {
+                Some(depth) => LocalHits::Two(t_in / speed, (t_in + depth) / speed),
+                None => LocalHits::One(t_in / speed), // the surface is open behind the hit
+            }
+        } else {
+            // the ray starts inside: find the exit ahead and the entry behind
+            let t_out = self.march(ray.origin, dir, -1.0);
+            let t_back = self.march(ray.origin, -dir, -1.0);
+            match (t_back, t_out) {
+                (Some(back), Some(out)) => LocalHits::Two(-back / speed, out / speed),
+                (None, Some(out)) => LocalHits::One(out / speed),
+                _ => LocalHits::None,
+            }
+        }
+    }
+
+    // Object-space normal, estimated from the gradient of the distance field
+    pub fn local_normal_at(&self, point: Point) -> Vector {
+        let h = Self::NORMAL_DELTA;
+        let d = |dx: Float, dy: Float, dz: Float| {
+            (self.distance)(point + Vector::new(dx, dy, dz))
+                - (self.distance)(point - Vector::new(dx, dy, dz))
+        };
+
+        Vector::new(d(h, 0.0, 0.0), d(0.0, h, 0.0), d(0.0, 0.0, h)).normalize()
+    }
+
+    pub fn local_contains_point(&self, point: Point) -> bool {
+        (self.distance)(point) <= 0.0
+    }
+
+    // Distance along the unit direction `dir` until the surface is crossed.
+    // `sign` is 1.0 when marching outside the surface and -1.0 when inside.
+    fn march(&self, origin: Point, dir: Vector, sign: Float) -> Option<Float> {
+        let mut t = 0.0;
+
+        for _ in 0..Self::MAX_STEPS {
+            let d = sign * (self.distance)(origin + dir * t);
+            if d < Self::HIT_EPSILON && t > Self::MIN_TRAVEL {
+                return Some(t);
+            }
+
+            // the distance field guarantees a step of |d| can't skip the surface
+            t += d.abs().max(Self::HIT_EPSILON);
+            if t > Self::MAX_DISTANCE {
+                return None;
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Transformation;
+    use crate::shapes::Shape;
+    use approx::assert_abs_diff_eq;
+
+    fn unit_sphere(p: Point) -> Float {
+        (p - Point::ORIGIN).magnitude() - 1.0
+    }
+
+    fn hits(h: LocalHits) -> Vec<Float> {
+        h.iter().collect()
+    }
+
+    #[test]
+    fn sdf_sphere_matches_analytic_sphere() {
+        let sdf = Sdf::new(unit_sphere);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = hits(sdf.local_intersect(r));
+
+        assert_eq!(xs.len(), 2);
+        assert_abs_diff_eq!(xs[0], 4.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(xs[1], 6.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn sdf_sphere_at_an_angle() {
+        let sdf = Sdf::new(unit_sphere);
+        let r = Ray::new(Point::new(0.0, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = hits(sdf.local_intersect(r));
+
+        let half_chord = Float::sqrt(0.75);
+        assert_abs_diff_eq!(xs[0], 5.0 - half_chord, epsilon = 1e-4);
+        assert_abs_diff_eq!(xs[1], 5.0 + half_chord, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn ray_misses_sdf_sphere() {
+        let sdf = Sdf::new(unit_sphere);
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(sdf.local_intersect(r), LocalHits::None);
+    }
+
+    #[test]
+    fn ray_originates_inside_sdf_sphere() {
+        let sdf = Sdf::new(unit_sphere);
+        let r = Ray::new(Point::ORIGIN, Vector::new(0.0, 0.0, 1.0));
+        let xs = hits(sdf.local_intersect(r));
+
+        assert_abs_diff_eq!(xs[0], -1.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(xs[1], 1.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn transformed_sdf_sphere_matches_scaled_sphere() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Shape::from(Sdf::new(unit_sphere))
+            .with_transform(Transformation::scaling(2.0, 2.0, 2.0));
+        let xs = s.intersect(r);
+
+        assert_abs_diff_eq!(xs.all()[0].t, 3.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(xs.all()[1].t, 7.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn sdf_normal_is_the_distance_gradient() {
+        let sdf = Sdf::new(unit_sphere);
+        let p = Point::new(
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
+        );
+
+        assert_abs_diff_eq!(sdf.local_normal_at(p), p - Point::ORIGIN);
+        assert_abs_diff_eq!(
+            sdf.local_normal_at(Point::new(1.0, 0.0, 0.0)),
+            Vector::new(1.0, 0.0, 0.0)
+        );
+    }
+}
@@ -1,27 +1,48 @@
 // use crate::Sphere;
+use crate::bounds::Bounds;
+use crate::color::Color;
 use crate::intersection::Intersection;
 use crate::intersection::Intersections;
 use crate::material::Material;
-use crate::matrix::Transformation;
+use crate::matrix::{NotInvertible, Transformation};
+use crate::pattern::{Pattern, sample_bilinear_wrapped};
 use crate::point::Point;
 use crate::ray::Ray;
-use crate::shapes::plane::Plane;
+use crate::sampling::orthonormal_basis;
+use crate::shapes::cone::Cone;
+use crate::shapes::cylinder::Cylinder;
+use crate::shapes::mesh::Mesh;
+use crate::shapes::plane::{Plane, Side};
+use crate::shapes::quad::Quad;
+use crate::shapes::sdf::Sdf;
 use crate::shapes::sphere::Sphere;
+use crate::shapes::triangle::Triangle;
+use crate::utils::EPSILON;
+use crate::utils::Float;
 use crate::vector::Vector;
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Shape {
     transform: Transformation,
     inverse_transform: Transformation, // cached inverse
     material: Material,
     geom: Geometry,
-    uv_map: Option<fn(Point) -> (f64, f64)>, // function to map points to UV coordinates
+    uv_map: Option<fn(Point) -> (Float, Float)>, // function to map points to UV coordinates
+    epsilon: Float, // world-space offset for over/under points, see `with_epsilon`
+    light_mask: Option<Vec<usize>>, // indices of the lights that shade this shape; None = all
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Geometry {
     Sphere(Sphere),
     Plane(Plane),
+    Quad(Quad),
+    Cylinder(Cylinder),
+    Cone(Cone),
+    Triangle(Box<Triangle>),
+    Mesh(Mesh),
+    Sdf(Sdf),
 }
 
 impl Shape {
@@ -32,6 +53,8 @@ impl Shape {
             material: Material::default(),
             geom: Geometry::Sphere(Sphere::new()),
             uv_map: Some(spherical_map),
+            epsilon: EPSILON,
+            light_mask: None,
         }
     }
 
@@ -44,6 +67,8 @@ impl Shape {
                 .with_refractive_index(1.5),
             geom: Geometry::Sphere(Sphere::new()),
             uv_map: Some(spherical_map),
+            epsilon: EPSILON,
+            light_mask: None,
         }
     }
 
@@ -54,18 +79,68 @@ impl Shape {
             material: Material::default(),
             geom: Geometry::Plane(Plane::new()),
             uv_map: None,
+            epsilon: EPSILON,
+            light_mask: None,
         }
     }
 
+    // a 2x2 panel in the xz plane; scale it with the transform
+    pub fn quad() -> Self {
+        Self {
+            transform: Transformation::identity(),
+            inverse_transform: Transformation::identity(),
+            material: Material::default(),
+            geom: Geometry::Quad(Quad::new()),
+            uv_map: Some(quad_map),
+            epsilon: EPSILON,
+            light_mask: None,
+        }
+    }
+
+    // an infinite, open cylinder of radius 1 around the y axis; see `Cylinder`
+    // for truncating and capping it
+    pub fn cylinder() -> Self {
+        Cylinder::new().into()
+    }
+
+    // an infinite, open double cone around the y axis; see `Cone`
+    pub fn cone() -> Self {
+        Cone::new().into()
+    }
+
+    // a ground plane checkered in `a` and `b`, with squares `scale` units wide
+    pub fn checker_floor(a: Color, b: Color, scale: Float) -> Self {
+        let pattern =
+            Pattern::checker(a, b).with_transform(Transformation::scaling(scale, scale, scale));
+
+        Shape::plane().with_material(Material {
+            pattern: Some(pattern),
+            specular: 0.0,
+            ..Material::default()
+        })
+    }
+
     fn with_geometry(mut self, g: Geometry) -> Self {
         self.geom = g;
         self
     }
 
-    pub fn with_transform(mut self, t: Transformation) -> Self {
+    // panics if `t` is not invertible; see `try_with_transform`
+    pub fn with_transform(self, t: Transformation) -> Self {
+        self.try_with_transform(t)
+            .expect("shape transform should be invertible")
+    }
+
+    pub fn try_with_transform(mut self, t: Transformation) -> Result<Self, NotInvertible> {
+        self.set_transform(t)?;
+        Ok(self)
+    }
+
+    // the shape is left unchanged if `t` is not invertible
+    pub fn set_transform(&mut self, t: Transformation) -> Result<(), NotInvertible> {
+        self.inverse_transform = t.try_inverse()?;
         self.transform = t;
-        self.inverse_transform = t.inverse();
-        self
+        Ok(())
     }
 
     pub fn transform(&self) -> &Transformation {
@@ -89,29 +164,97 @@ impl Shape {
         &mut self.material
     }
 
-    pub fn uv_map(&self) -> Option<fn(Point) -> (f64, f64)> {
+    // override the surface offset used to avoid self-intersection (acne),
+    // e.g. a larger value for shapes that span huge distances
+    pub fn with_epsilon(mut self, epsilon: Float) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn epsilon(&self) -> Float {
+        self.epsilon
+    }
+
+    // only the lights at these indices in `World::lights` will shade the shape
+    pub fn with_light_mask(mut self, lights: Vec<usize>) -> Self {
+        self.light_mask = Some(lights);
+        self
+    }
+
+    pub fn light_mask(&self) -> Option<&[usize]> {
+        self.light_mask.as_deref()
+    }
+
+    pub fn is_lit_by(&self, light_index: usize) -> bool {
+        self.light_mask
+            .as_ref()
+            .is_none_or(|mask| mask.contains(&light_index))
+    }
+
+    pub fn uv_map(&self) -> Option<fn(Point) -> (Float, Float)> {
         self.uv_map
     }
 
     pub fn intersect<'a>(&'a self, ray_world: Ray) -> Intersections<'a> {
-        let ray_obj = ray_world.transform(self.inverse_transform);
-        let hits = match &self.geom {
-            Geometry::Sphere(s) => s.local_intersect(ray_obj).iter().collect::<Vec<_>>(),
-            Geometry::Plane(p) => p.local_intersect(ray_obj).iter().collect::<Vec<_>>(),
-        };
+        let mut list = Vec::new();
+        self.for_each_hit(ray_world, |t, triangle| {
+            list.push(Intersection {
+                t,
+                s: self,
+                triangle,
+            })
+        });
+        Intersections::from_sorted(list)
+    }
 
-        if hits.is_empty() {
-            Intersections::empty()
-        } else {
-            let list = hits
+    // call `f` with the t of every hit, in ascending order, without collecting
+    // them; meshes also pass the index of the triangle that was hit
+    pub(crate) fn for_each_hit(&self, ray_world: Ray, mut f: impl FnMut(Float, Option<usize>)) {
+        // not normalized, so object-space t are also world-space t
+        let ray_obj = ray_world.transform(self.inverse_transform);
+        let hit = |t| f(t, None);
+        match &self.geom {
+            Geometry::Sphere(s) => s.local_intersect(ray_obj).iter().for_each(hit),
+            Geometry::Plane(p) => self
+                .cull_back_face(p.local_intersect_sided(ray_obj))
                 .into_iter()
-                .map(|t| Intersection { t, s: self })
-                .collect::<Vec<_>>();
-            Intersections::from_sorted(list)
+                .for_each(hit),
+            Geometry::Quad(q) => self
+                .cull_back_face(q.local_intersect_sided(ray_obj))
+                .into_iter()
+                .for_each(hit),
+            Geometry::Cylinder(c) => c.local_intersect(ray_obj).into_iter().for_each(hit),
+            Geometry::Cone(c) => c.local_intersect(ray_obj).into_iter().for_each(hit),
+            Geometry::Triangle(t) => t.local_intersect(ray_obj).iter().for_each(hit),
+            Geometry::Sdf(s) => s.local_intersect(ray_obj).iter().for_each(hit),
+            Geometry::Mesh(m) => m
+                .local_intersect(ray_obj)
+                .into_iter()
+                .for_each(|(t, triangle)| f(t, Some(triangle))),
+        }
+    }
+
+    // unlit color of the surface at a world point: the material's pattern
+    // sample, or its flat color
+    pub fn surface_color_at(&self, point: Point) -> Color {
+        match &self.material.pattern {
+            Some(pattern) => pattern.pattern_at_object(self, point),
+            None => self.material.color,
         }
     }
 
+    // single-sided materials can only be seen from the top face
+    fn cull_back_face(&self, hit: Option<(Float, Side)>) -> Option<Float> {
+        hit.filter(|&(_, side)| side == Side::Top || self.material.double_sided)
+            .map(|(t, _)| t)
+    }
+
     pub fn normal_at(&self, p_world: Point) -> Vector {
+        self.normal_at_triangle(p_world, None)
+    }
+
+    // as `normal_at`, on the mesh triangle an intersection recorded, if any
+    pub fn normal_at_triangle(&self, p_world: Point, triangle: Option<usize>) -> Vector {
         // world -> object space once, here
         let p_obj = self.inverse_transform * p_world;
 
@@ -119,11 +262,147 @@ impl Shape {
         let n_obj = match &self.geom {
             Geometry::Sphere(s) => s.local_normal_at(p_obj),
             Geometry::Plane(p) => p.local_normal_at(p_obj),
+            Geometry::Quad(q) => q.local_normal_at(p_obj),
+            Geometry::Cylinder(c) => c.local_normal_at(p_obj),
+            Geometry::Cone(c) => c.local_normal_at(p_obj),
+            Geometry::Triangle(t) => t.local_normal_at(p_obj),
+            Geometry::Sdf(f) => f.local_normal_at(p_obj),
+            Geometry::Mesh(m) => m.local_normal_at(p_obj, triangle),
+        };
+        let n_obj = self.apply_normal_map(p_obj, n_obj);
+
+        self.normal_to_world(n_obj)
+    }
+
+    // the true surface normal, without vertex-normal interpolation or normal
+    // mapping; points are offset along this so they stay off the surface
+    pub fn geometric_normal_at(&self, p_world: Point) -> Vector {
+        self.geometric_normal_at_triangle(p_world, None)
+    }
+
+    // as `geometric_normal_at`, on the mesh triangle an intersection recorded, if any
+    pub fn geometric_normal_at_triangle(&self, p_world: Point, triangle: Option<usize>) -> Vector {
+        let p_obj = self.inverse_transform * p_world;
+
+        let n_obj = match &self.geom {
+            Geometry::Triangle(t) => t.normal,
+            Geometry::Mesh(m) => m.local_face_normal_at(p_obj, triangle),
+            Geometry::Sphere(s) => s.local_normal_at(p_obj),
+            Geometry::Plane(p) => p.local_normal_at(p_obj),
+            Geometry::Quad(q) => q.local_normal_at(p_obj),
+            Geometry::Cylinder(c) => c.local_normal_at(p_obj),
+            Geometry::Cone(c) => c.local_normal_at(p_obj),
+            Geometry::Sdf(f) => f.local_normal_at(p_obj),
         };
 
+        self.normal_to_world(n_obj)
+    }
+
+    fn normal_to_world(&self, n_obj: Vector) -> Vector {
         // transform normal back to world space using (inverse^T)
+        // mirroring transforms (negative determinant) need no special casing here:
+        // the inverse-transpose maps an outward object normal to an outward world
+        // normal regardless of handedness. Only winding-derived normals would flip.
         (self.inverse_transform.transpose() * n_obj).normalize()
     }
+
+    // tilt an object-space normal by the material's normal map, if any.
+    // The tangent frame follows the directions in which u and v increase.
+    fn apply_normal_map(&self, p_obj: Point, n_obj: Vector) -> Vector {
+        let (Some(map), Some(uv_fn)) = (&self.material.normal_map, self.uv_map) else {
+            return n_obj;
+        };
+
+        // uv change along two directions in the tangent plane
+        let n = n_obj.normalize();
+        let (a, b) = orthonormal_basis(n);
+        let (u0, v0) = uv_fn(p_obj);
+        let delta = |p: Point| {
+            let (u, v) = uv_fn(p);
+            let du = u - u0;
+            // don't let the u = 0/1 seam look like a huge jump
+            let du = if du > 0.5 {
+                du - 1.0
+            } else if du < -0.5 {
+                du + 1.0
+            } else {
+                du
+            };
+            (du, v - v0)
+        };
+        let (du_a, dv_a) = delta(p_obj + a * 1e-4);
+        let (du_b, dv_b) = delta(p_obj + b * 1e-4);
+
+        // invert the uv jacobian to find the surface directions of +u and +v
+        let det = du_a * dv_b - du_b * dv_a;
+        if det.abs() < 1e-12 {
+            return n_obj; // degenerate mapping, e.g. at a pole
+        }
+        let tangent = ((a * dv_b - b * dv_a) / det).normalize();
+        let bitangent = ((b * du_a - a * du_b) / det).normalize();
+
+        // RGB in [0, 1] encodes each tangent-space component in [-1, 1]
+        let c = sample_bilinear_wrapped(map, u0, v0);
+        tangent * (2.0 * c.red - 1.0) + bitangent * (2.0 * c.green - 1.0) + n * (2.0 * c.blue - 1.0)
+    }
+
+    // object-space box around the geometry; None if it is unbounded
+    pub fn local_bounds(&self) -> Option<Bounds> {
+        let finite = |b: Bounds| {
+            let all = [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z];
+            all.iter().all(|v| v.is_finite()).then_some(b)
+        };
+
+        match &self.geom {
+            Geometry::Sphere(_) => Some(Bounds::new(
+                Point::new(-1.0, -1.0, -1.0),
+                Point::new(1.0, 1.0, 1.0),
+            )),
+            Geometry::Plane(_) | Geometry::Sdf(_) => None,
+            Geometry::Quad(_) => Some(Bounds::new(
+                Point::new(-1.0, 0.0, -1.0),
+                Point::new(1.0, 0.0, 1.0),
+            )),
+            Geometry::Cylinder(c) => finite(Bounds::new(
+                Point::new(-1.0, c.minimum, -1.0),
+                Point::new(1.0, c.maximum, 1.0),
+            )),
+            Geometry::Cone(c) => {
+                let r = c.minimum.abs().max(c.maximum.abs());
+                finite(Bounds::new(
+                    Point::new(-r, c.minimum, -r),
+                    Point::new(r, c.maximum, r),
+                ))
+            }
+            Geometry::Triangle(t) => Bounds::from_points([t.p1, t.p2, t.p3]),
+            Geometry::Mesh(m) => {
+                Bounds::from_points(m.triangles.iter().flat_map(|t| [t.p1, t.p2, t.p3]))
+            }
+        }
+    }
+
+    // world-space box around the geometry, after the shape's transform;
+    // None if it is unbounded
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.local_bounds().map(|b| b.transform(self.transform))
+    }
+
+    // is the world-space point inside the shape's volume?
+    pub fn contains_point(&self, p_world: Point) -> bool {
+        let p_obj = self.inverse_transform * p_world;
+
+        match &self.geom {
+            Geometry::Sphere(s) => s.local_contains_point(p_obj),
+            Geometry::Plane(p) => p.local_contains_point(p_obj),
+            Geometry::Quad(q) => q.local_contains_point(p_obj),
+            Geometry::Cylinder(c) => c.local_contains_point(p_obj),
+            Geometry::Cone(c) => c.local_contains_point(p_obj),
+            // a lone triangle has no interior
+            Geometry::Triangle(_) => false,
+            Geometry::Sdf(f) => f.local_contains_point(p_obj),
+            Geometry::Mesh(m) => m.local_contains_point(p_obj),
+        }
+    }
 }
 
 impl From<Sphere> for Shape {
@@ -138,7 +417,43 @@ impl From<Plane> for Shape {
     }
 }
 
-fn spherical_map(point: Point) -> (f64, f64) {
+impl From<Quad> for Shape {
+    fn from(q: Quad) -> Self {
+        Shape::quad().with_geometry(Geometry::Quad(q))
+    }
+}
+
+impl From<Cylinder> for Shape {
+    fn from(c: Cylinder) -> Self {
+        Shape::plane().with_geometry(Geometry::Cylinder(c))
+    }
+}
+
+impl From<Cone> for Shape {
+    fn from(c: Cone) -> Self {
+        Shape::plane().with_geometry(Geometry::Cone(c))
+    }
+}
+
+impl From<Sdf> for Shape {
+    fn from(f: Sdf) -> Self {
+        Shape::plane().with_geometry(Geometry::Sdf(f))
+    }
+}
+
+impl From<Mesh> for Shape {
+    fn from(m: Mesh) -> Self {
+        Shape::plane().with_geometry(Geometry::Mesh(m))
+    }
+}
+
+impl From<Triangle> for Shape {
+    fn from(t: Triangle) -> Self {
+        Shape::plane().with_geometry(Geometry::Triangle(Box::new(t)))
+    }
+}
+
+fn spherical_map(point: Point) -> (Float, Float) {
     //   compute the azimuthal angle
     //   -π < theta <= π
     //   angle increases clockwise as viewed from above,
@@ -156,7 +471,7 @@ fn spherical_map(point: Point) -> (f64, f64) {
     let phi = (point.y / radius).acos();
 
     // -0.5 < raw_u <= 0.5
-    let raw_u = theta / (2.0 * std::f64::consts::PI);
+    let raw_u = theta / (2.0 * crate::utils::consts::PI);
 
     // 0 <= u < 1
     // here's also where we fix the direction of u. Subtract it from 1,
@@ -166,13 +481,21 @@ fn spherical_map(point: Point) -> (f64, f64) {
     // we want v to be 0 at the south pole of the sphere,
     // and 1 at the north pole, so we have to "flip it over"
     // by subtracting it from 1.
-    let v = 1.0 - (phi / std::f64::consts::PI);
+    let v = 1.0 - (phi / crate::utils::consts::PI);
     (u, v)
 }
 
+// stretch the quad's -1..1 extent in x and z over 0..1, so one texture
+// tile covers the whole panel
+fn quad_map(point: Point) -> (Float, Float) {
+    ((point.x + 1.0) / 2.0, (point.z + 1.0) / 2.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::ROUNDING_EPSILON;
+
     #[test]
     fn using_spherical_mapping_on_3d_point() {
         assert_eq!(spherical_map(Point::new(0.0, 0.0, -1.0)), (0.0, 0.5));
@@ -182,16 +505,181 @@ mod tests {
         assert_eq!(spherical_map(Point::new(0.0, 1.0, 0.0)), (0.5, 1.0));
         assert_eq!(spherical_map(Point::new(0.0, -1.0, 0.0)), (0.5, 0.0));
         assert_eq!(
-            spherical_map(Point::new(2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0, 0.0)),
+            spherical_map(Point::new(
+                Float::sqrt(2.0) / 2.0,
+                Float::sqrt(2.0) / 2.0,
+                0.0
+            )),
             (0.25, 0.75)
         );
     }
 
+    #[test]
+    fn world_bounds_follow_the_transform() {
+        let s = Shape::sphere().with_transform(
+            Transformation::translation(0.0, 2.0, 0.0) * Transformation::scaling(2.0, 1.0, 1.0),
+        );
+        let b = s.bounds().unwrap();
+
+        assert_eq!(b.min, Point::new(-2.0, 1.0, -1.0));
+        assert_eq!(b.max, Point::new(2.0, 3.0, 1.0));
+        assert_eq!(Shape::plane().bounds(), None);
+    }
+
+    #[test]
+    fn quad_uv_spans_the_clipped_extent() {
+        let q = Shape::quad().with_transform(Transformation::scaling(3.0, 1.0, 2.0));
+        let uv_at = |x: Float, z: Float| {
+            let r = Ray::new(Point::new(x, 1.0, z), Vector::new(0.0, -1.0, 0.0));
+            let hit = q.intersect(r).hit().map(|i| r.position(i.t)).unwrap();
+            q.uv_map().unwrap()(*q.inverse_transform() * hit)
+        };
+
+        assert_eq!(uv_at(0.0, 0.0), (0.5, 0.5));
+        assert_eq!(uv_at(-3.0, -2.0), (0.0, 0.0));
+        assert_eq!(uv_at(3.0, 2.0), (1.0, 1.0));
+        assert_eq!(uv_at(-3.0, 2.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn surface_color_follows_the_material_pattern() {
+        let striped = Shape::sphere().with_material(
+            Material::new().with_pattern(Pattern::striped(Color::WHITE, Color::BLACK)),
+        );
+        let colors: Vec<Color> = [-1.5, -0.5, 0.5, 1.5]
+            .into_iter()
+            .map(|x| striped.surface_color_at(Point::new(x, 0.0, 0.0)))
+            .collect();
+        assert_eq!(
+            colors,
+            [Color::WHITE, Color::BLACK, Color::WHITE, Color::BLACK]
+        );
+
+        let plain = Shape::sphere().with_material(Material::new().with_color(Color::RED));
+        assert_eq!(
+            plain.surface_color_at(Point::new(0.0, 1.0, 0.0)),
+            Color::RED
+        );
+    }
+
+    #[test]
+    fn checker_floor_is_a_scaled_checkered_plane() {
+        let floor = Shape::checker_floor(Color::WHITE, Color::BLACK, 2.0);
+
+        assert!(matches!(floor.geom, Geometry::Plane(_)));
+        assert_eq!(
+            floor.material().pattern,
+            Some(
+                Pattern::checker(Color::WHITE, Color::BLACK)
+                    .with_transform(Transformation::scaling(2.0, 2.0, 2.0))
+            )
+        );
+        assert_eq!(floor.material().specular, 0.0);
+        assert_eq!(
+            floor.surface_color_at(Point::new(1.9, 0.0, 0.0)),
+            Color::WHITE
+        );
+        assert_eq!(
+            floor.surface_color_at(Point::new(2.1, 0.0, 0.0)),
+            Color::BLACK
+        );
+    }
+
+    #[test]
+    fn single_sided_plane_is_invisible_from_below() {
+        let one_sided = Shape::plane().with_material(Material::new().with_double_sided(false));
+        let down = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let up = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(one_sided.intersect(down).all().len(), 1);
+        assert!(one_sided.intersect(up).is_empty());
+        assert_eq!(Shape::plane().intersect(up).all().len(), 1);
+    }
+
+    #[test]
+    fn points_inside_and_outside_a_transformed_sphere() {
+        let s = Shape::sphere().with_transform(
+            Transformation::translation(0.0, 2.0, 0.0) * Transformation::scaling(2.0, 1.0, 1.0),
+        );
+
+        assert!(s.contains_point(Point::new(0.0, 2.0, 0.0)));
+        assert!(s.contains_point(Point::new(1.9, 2.0, 0.0)));
+        assert!(s.contains_point(Point::new(0.0, 2.9, 0.0)));
+        assert!(!s.contains_point(Point::ORIGIN));
+        assert!(!s.contains_point(Point::new(0.0, 2.0, 1.1)));
+        assert!(!s.contains_point(Point::new(2.1, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn plane_contains_no_points() {
+        let p = Shape::plane();
+
+        assert!(!p.contains_point(Point::ORIGIN));
+        assert!(!p.contains_point(Point::new(0.0, -1.0, 0.0)));
+    }
+
+    #[test]
+    fn zero_scale_transform_is_an_error() {
+        let s = Shape::sphere().try_with_transform(Transformation::scaling(0.0, 0.0, 0.0));
+        assert_eq!(s, Err(NotInvertible));
+
+        let mut s = Shape::sphere();
+        let t = Transformation::translation(1.0, 0.0, 0.0);
+        s.set_transform(t).unwrap();
+        assert!(
+            s.set_transform(Transformation::scaling(1.0, 0.0, 1.0))
+                .is_err()
+        );
+        assert_eq!(s.transform(), &t);
+    }
+
     #[test]
     fn sphere_with_glassy_material() {
         let s = Shape::glass_sphere();
         assert_eq!(s.transform(), &Transformation::identity());
-        assert_eq!(s.material().transparency, 1.0);
-        assert_eq!(s.material().refractive_index, 1.5);
+        assert_eq!(
+            s.material(),
+            &Material {
+                transparency: 1.0,
+                refractive_index: 1.5,
+                ..Material::default()
+            }
+        );
+    }
+
+    #[test]
+    fn normal_maps_perturb_the_shading_normal() {
+        use crate::canvas::Canvas;
+        use crate::color::Color;
+        use crate::point_light::PointLight;
+        use approx::assert_abs_diff_eq;
+
+        let map = |c: Color| {
+            let mut canvas = Canvas::empty(4, 4);
+            canvas.clear(c);
+            canvas
+        };
+        let flat = Shape::sphere()
+            .with_material(Material::new().with_normal_map(map(Color::new(0.5, 0.5, 1.0))));
+        let tilted = Shape::sphere()
+            .with_material(Material::new().with_normal_map(map(Color::new(0.8, 0.5, 0.8))));
+        let plain = Shape::sphere();
+
+        let p = Point::new(0.6, 0.0, -0.8);
+        assert_abs_diff_eq!(
+            flat.normal_at(p),
+            plain.normal_at(p),
+            epsilon = ROUNDING_EPSILON
+        );
+
+        let n = tilted.normal_at(p);
+        assert_abs_diff_eq!(n.magnitude(), 1.0, epsilon = ROUNDING_EPSILON);
+        assert!(!n.approx_eq(&plain.normal_at(p), 1e-3));
+
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let shade = |s: &Shape| s.material().shade(s, p, light, eye, s.normal_at(p), false);
+        assert_abs_diff_eq!(shade(&flat), shade(&plain), epsilon = 1e-9);
+        assert!(!shade(&tilted).approx_eq(&shade(&plain), 1e-3));
     }
 }
@@ -1,6 +1,8 @@
 use crate::intersection::LocalHits;
 use crate::point::Point;
 use crate::ray::Ray;
+use crate::shapes::{Mesh, Shape, Triangle};
+use crate::utils::Float;
 use crate::vector::Vector;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -23,7 +25,7 @@ impl Sphere {
         let sphere_to_ray = ray_obj.origin - Point::ORIGIN;
         let a = ray_obj.direction.dot(ray_obj.direction);
         let b = 2.0 * ray_obj.direction.dot(sphere_to_ray);
-        let c = sphere_to_ray.dot(sphere_to_ray) - 1.0_f64; // Sphere::RADIUS.powi(2)
+        let c = sphere_to_ray.dot(sphere_to_ray) - (1.0 as Float); // Sphere::RADIUS.powi(2)
 
         let discriminant = b * b - 4.0 * a * c;
         if discriminant < 0.0 {
@@ -48,18 +50,55 @@ impl Sphere {
     pub fn local_normal_at(&self, point: Point) -> Vector {
         (point - Point::ORIGIN).normalize()
     }
+
+    // approximate the unit sphere with a mesh of smooth triangles, split into
+    // `lat_segments` bands from pole to pole and `lon_segments` around the y axis
+    pub fn tessellate(&self, lat_segments: usize, lon_segments: usize) -> Shape {
+        let vertex = |i: usize, j: usize| {
+            let phi = crate::utils::consts::PI * i as Float / lat_segments as Float;
+            let theta = 2.0 * crate::utils::consts::PI * j as Float / lon_segments as Float;
+            let p = Point::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+            // on the unit sphere the normal is the position itself
+            (p, p - Point::ORIGIN)
+        };
+        let triangle = |(p1, n1), (p2, n2), (p3, n3)| Triangle::smooth(p1, p2, p3, n1, n2, n3);
+
+        let mut triangles = Vec::new();
+        for i in 0..lat_segments {
+            for j in 0..lon_segments {
+                let (a, b) = (vertex(i, j), vertex(i + 1, j));
+                let (c, d) = (vertex(i + 1, j + 1), vertex(i, j + 1));
+                // the bands touching the poles collapse to a single triangle
+                if i + 1 < lat_segments {
+                    triangles.push(triangle(a, b, c));
+                }
+                if i > 0 {
+                    triangles.push(triangle(a, c, d));
+                }
+            }
+        }
+
+        Mesh::new(triangles).into()
+    }
+
+    // Is the object-space point inside (or on) the unit sphere?
+    pub fn local_contains_point(&self, point: Point) -> bool {
+        let v = point - Point::ORIGIN;
+        v.dot(v) <= 1.0
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::camera::Camera;
     use crate::color::Color;
     use crate::material::Material;
     use crate::matrix::Matrix;
     use crate::matrix::Transformation;
     use crate::point_light::PointLight;
-    use crate::shapes::Shape;
     use crate::vector::Vector;
+    use crate::world::World;
     use approx::assert_abs_diff_eq;
 
     #[test]
@@ -160,14 +199,14 @@ mod tests {
     fn normal_on_sphere_at_non_axial_point() {
         let s = Shape::from(Sphere::new());
         let n = s.normal_at(Point::new(
-            3.0_f64.sqrt() / 3.0,
-            3.0_f64.sqrt() / 3.0,
-            3.0_f64.sqrt() / 3.0,
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
         ));
         let expected = Vector::new(
-            3.0_f64.sqrt() / 3.0,
-            3.0_f64.sqrt() / 3.0,
-            3.0_f64.sqrt() / 3.0,
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
         );
         assert_eq!(n, expected);
     }
@@ -176,12 +215,12 @@ mod tests {
     fn the_normal_is_a_normalized_vector() {
         let s = Shape::from(Sphere::new());
         let n = s.normal_at(Point::new(
-            3.0_f64.sqrt() / 3.0,
-            3.0_f64.sqrt() / 3.0,
-            3.0_f64.sqrt() / 3.0,
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
+            Float::sqrt(3.0) / 3.0,
         ));
 
-        assert_eq!(n, n.normalize());
+        assert_abs_diff_eq!(n, n.normalize());
     }
 
     #[test]
@@ -198,15 +237,39 @@ mod tests {
     #[test]
     fn normal_on_transformed_sphere() {
         let s = Shape::from(Sphere::new()).with_transform(
-            Matrix::scaling(1.0, 0.5, 1.0) * Matrix::rotation_z(std::f64::consts::PI / 5.0),
+            Matrix::scaling(1.0, 0.5, 1.0) * Matrix::rotation_z(crate::utils::consts::PI / 5.0),
         );
 
-        let n = s.normal_at(Point::new(0.0, 2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0));
+        let n = s.normal_at(Point::new(
+            0.0,
+            Float::sqrt(2.0) / 2.0,
+            Float::sqrt(2.0) / 2.0,
+        ));
 
         let expected = Vector::new(0.0, 0.97014, 0.24254);
         assert_abs_diff_eq!(n, expected);
     }
 
+    #[test]
+    fn normal_on_mirrored_sphere_points_outward() {
+        let s = Shape::from(Sphere::new()).with_transform(
+            Transformation::translation(2.0, 0.0, 0.0) * Matrix::scaling(-1.0, 1.0, 1.0),
+        );
+        assert!(s.transform().determinant() < 0.0);
+
+        let center = Point::new(2.0, 0.0, 0.0);
+        for p in [
+            Point::new(3.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(2.0, 1.0, 0.0),
+            Point::new(2.0, 0.0, -1.0),
+        ] {
+            let n = s.normal_at(p);
+            assert_abs_diff_eq!(n, (p - center).normalize());
+            assert!(n.dot(p - center) > 0.0);
+        }
+    }
+
     #[test]
     fn sphere_has_default_material() {
         let s = Shape::from(Sphere::new());
@@ -239,7 +302,7 @@ mod tests {
     #[test]
     fn lighting_with_the_eye_between_light_and_surface_at_an_angle() {
         let position = Point::ORIGIN;
-        let eye = Vector::new(0.0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
+        let eye = Vector::new(0.0, Float::sqrt(2.0) / 2.0, -Float::sqrt(2.0) / 2.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE);
         let s = Shape::from(Sphere::new());
@@ -265,7 +328,7 @@ mod tests {
     #[test]
     fn lighting_with_the_eye_in_the_path_of_the_reflection_vector() {
         let position = Point::ORIGIN;
-        let eye = Vector::new(0.0, -2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
+        let eye = Vector::new(0.0, -Float::sqrt(2.0) / 2.0, -Float::sqrt(2.0) / 2.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::WHITE);
         let s = Shape::from(Sphere::new());
@@ -301,4 +364,28 @@ mod tests {
             .shade(&Shape::sphere(), position, light, eye, normal, in_shadow);
         assert_eq!(color, Color::new(0.1, 0.1, 0.1));
     }
+
+    #[test]
+    fn tessellated_sphere_matches_the_analytic_silhouette() {
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+        let analytic = World::new(vec![Shape::sphere()], light);
+        let tessellated = World::new(vec![Sphere::new().tessellate(32, 64)], light);
+        let mut camera = Camera::new(41, 41, crate::utils::consts::PI / 3.0);
+        camera.transform = Matrix::view_transform(
+            Point::new(0.0, 0.0, -4.0),
+            Point::ORIGIN,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let expected = camera.render_object_ids(&analytic);
+        let actual = camera.render_object_ids(&tessellated);
+
+        // facets sit just inside the sphere, so only edge pixels may differ;
+        // f32's coarser epsilon loses a few more grazing hits on the facets
+        let mismatched = expected.iter().zip(&actual).filter(|(a, b)| a != b).count();
+        let covered = expected.iter().filter(|id| id.is_some()).count();
+        let allowed = if cfg!(feature = "f32") { 12 } else { 4 };
+        assert!(covered > 0);
+        assert!(mismatched <= allowed, "{mismatched} pixels differ");
+    }
 }
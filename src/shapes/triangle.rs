@@ -0,0 +1,171 @@
+use crate::intersection::LocalHits;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::utils::EPSILON;
+use crate::utils::Float;
+use crate::vector::Vector;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+    pub e1: Vector,
+    pub e2: Vector,
+    pub normal: Vector,
+    // per-vertex normals for smooth shading (n1, n2, n3)
+    pub vertex_normals: Option<[Vector; 3]>,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalize(),
+            vertex_normals: None,
+        }
+    }
+
+    // a triangle whose normal is interpolated from the normals at each vertex
+    pub fn smooth(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        Self {
+            vertex_normals: Some([n1, n2, n3]),
+            ..Self::new(p1, p2, p3)
+        }
+    }
+
+    // Compute the intersection of a ray and a triangle (Möller–Trumbore)
+    // Assumes `ray` is already in object space
+    pub fn local_intersect(&self, ray: Ray) -> LocalHits {
+        let dir_cross_e2 = ray.direction.cross(self.e2);
+        let det = self.e1.dot(dir_cross_e2);
+        if det.abs() < EPSILON {
+            return LocalHits::None; // parallel
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return LocalHits::None; // misses the p1-p3 edge
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(self.e1);
+        let v = f * ray.direction.dot(origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return LocalHits::None; // misses the p1-p2 or p2-p3 edge
+        }
+
+        LocalHits::One(f * self.e2.dot(origin_cross_e1))
+    }
+
+    // Object-space normal
+    pub fn local_normal_at(&self, point: Point) -> Vector {
+        match self.vertex_normals {
+            None => self.normal,
+            Some([n1, n2, n3]) => {
+                let (u, v) = self.barycentric(point);
+                n2 * u + n3 * v + n1 * (1.0 - u - v)
+            }
+        }
+    }
+
+    // barycentric (u, v) of a point on the triangle; u weights p2 and v weights p3
+    pub(crate) fn barycentric(&self, point: Point) -> (Float, Float) {
+        let p = point - self.p1;
+        let d11 = self.e1.dot(self.e1);
+        let d12 = self.e1.dot(self.e2);
+        let d22 = self.e2.dot(self.e2);
+        let dp1 = p.dot(self.e1);
+        let dp2 = p.dot(self.e2);
+        let denom = d11 * d22 - d12 * d12;
+
+        (
+            (d22 * dp1 - d12 * dp2) / denom,
+            (d11 * dp2 - d12 * dp1) / denom,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::Shape;
+    use approx::assert_abs_diff_eq;
+
+    fn book_triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = book_triangle();
+
+        assert_abs_diff_eq!(t.e1, Vector::new(-1.0, -1.0, 0.0));
+        assert_abs_diff_eq!(t.e2, Vector::new(1.0, -1.0, 0.0));
+        assert_abs_diff_eq!(t.normal, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal_of_triangle_is_constant() {
+        let t = book_triangle();
+
+        assert_eq!(t.local_normal_at(Point::new(0.0, 0.5, 0.0)), t.normal);
+        assert_eq!(t.local_normal_at(Point::new(-0.5, 0.75, 0.0)), t.normal);
+        assert_eq!(t.local_normal_at(Point::new(0.5, 0.25, 0.0)), t.normal);
+    }
+
+    #[test]
+    fn intersecting_ray_parallel_to_triangle() {
+        let t = book_triangle();
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(t.local_intersect(r), LocalHits::None);
+    }
+
+    #[test]
+    fn ray_misses_each_edge() {
+        let t = book_triangle();
+        for origin in [
+            Point::new(1.0, 1.0, -2.0),
+            Point::new(-1.0, 1.0, -2.0),
+            Point::new(0.0, -1.0, -2.0),
+        ] {
+            let r = Ray::new(origin, Vector::new(0.0, 0.0, 1.0));
+            assert_eq!(t.local_intersect(r), LocalHits::None);
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_triangle() {
+        let t = book_triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(t.local_intersect(r), LocalHits::One(2.0));
+    }
+
+    #[test]
+    fn smooth_triangle_interpolates_the_normal() {
+        let t = Triangle::smooth(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        // the point at u = 0.45, v = 0.25
+        let s = Shape::from(t);
+        let n = s.normal_at(Point::new(-0.2, 0.3, 0.0));
+
+        assert_abs_diff_eq!(n, Vector::new(-0.5547, 0.83205, 0.0));
+    }
+}
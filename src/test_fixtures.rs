@@ -0,0 +1,24 @@
+// Scenes from the book shared by the unit tests. Integration tests and
+// examples can use them by enabling the `test-util` feature.
+use crate::color::Color;
+use crate::material::Material;
+use crate::matrix::Transformation;
+use crate::point::Point;
+use crate::point_light::PointLight;
+use crate::shapes::Shape;
+use crate::world::World;
+
+// the book's default world: a white light up and to the left of a green
+// unit sphere, with a half-size sphere inside it
+pub fn default_world() -> World {
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+    let s1 = Shape::sphere().with_material(Material::default_world_sphere());
+    let s2 = Shape::sphere().with_transform(Transformation::scaling(0.5, 0.5, 0.5));
+    World::new(vec![s1, s2], light)
+}
+
+impl Default for World {
+    fn default() -> Self {
+        default_world()
+    }
+}
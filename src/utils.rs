@@ -1 +1,24 @@
-pub const EPSILON: f64 = 1e-5;
+// The floating point type used for all geometry and color math; `f64`
+// unless the `f32` feature trades precision for memory in large renders
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+// the mathematical constants (PI, FRAC_PI_2, ...) for `Float`
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+// surfaces and comparisons are fuzzed by this much; looser under `f32`,
+// whose ~7 significant digits leave 1e-5 inside the rounding noise
+#[cfg(not(feature = "f32"))]
+pub const EPSILON: Float = 1e-5;
+#[cfg(feature = "f32")]
+pub const EPSILON: Float = 1e-3;
+
+// tolerance for test results that should agree up to rounding error:
+// 1e-10 in f64, 1e-6 in f32
+#[cfg(test)]
+pub(crate) const ROUNDING_EPSILON: Float = EPSILON * EPSILON;
@@ -3,16 +3,18 @@ use std::ops;
 use approx::AbsDiffEq;
 
 use crate::point::Point;
+use crate::utils::EPSILON;
+use crate::utils::Float;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vector {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
 }
 
 impl Vector {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
         Self { x, y, z }
     }
 
@@ -26,11 +28,11 @@ impl Vector {
         *self - normal * 2.0 * self.dot(normal)
     }
 
-    pub fn magnitude(&self) -> f64 {
+    pub fn magnitude(&self) -> Float {
         (self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
     }
 
-    pub fn dot(&self, other: Self) -> f64 {
+    pub fn dot(&self, other: Self) -> Float {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
@@ -41,6 +43,39 @@ impl Vector {
             self.x * other.y - self.y * other.x,
         )
     }
+
+    // the component of self along `other`
+    pub fn project_onto(&self, other: Self) -> Self {
+        other * (self.dot(other) / other.dot(other))
+    }
+
+    // the component of self perpendicular to `other`
+    pub fn reject_from(&self, other: Self) -> Self {
+        *self - self.project_onto(other)
+    }
+
+    // linear interpolation: `a` at t = 0, `b` at t = 1;
+    // normalize the result when interpolating directions
+    pub fn lerp(a: Vector, b: Vector, t: Float) -> Vector {
+        a + (b - a) * t
+    }
+
+    // spherical interpolation between unit vectors at a constant angular rate.
+    // Falls back to `lerp` when they are (anti)parallel and the arc is undefined.
+    pub fn slerp(a: Vector, b: Vector, t: Float) -> Vector {
+        let theta = a.dot(b).clamp(-1.0, 1.0).acos();
+        let sin_theta = theta.sin();
+        if sin_theta.abs() < EPSILON {
+            return Vector::lerp(a, b, t);
+        }
+
+        (a * ((1.0 - t) * theta).sin() + b * (t * theta).sin()) / sin_theta
+    }
+
+    // compare against `other` using a caller-chosen tolerance
+    pub fn approx_eq(&self, other: &Self, epsilon: Float) -> bool {
+        self.abs_diff_eq(other, epsilon)
+    }
 }
 
 impl ops::Add<Self> for Vector {
@@ -67,6 +102,18 @@ impl ops::Sub for Vector {
     }
 }
 
+impl ops::AddAssign for Vector {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl ops::SubAssign for Vector {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
 impl ops::Neg for Vector {
     type Output = Self;
 
@@ -75,27 +122,27 @@ impl ops::Neg for Vector {
     }
 }
 
-impl ops::Mul<f64> for Vector {
+impl ops::Mul<Float> for Vector {
     type Output = Self;
 
-    fn mul(self, other: f64) -> Self {
+    fn mul(self, other: Float) -> Self {
         Self::new(self.x * other, self.y * other, self.z * other)
     }
 }
 
-impl ops::Div<f64> for Vector {
+impl ops::Div<Float> for Vector {
     type Output = Self;
 
-    fn div(self, other: f64) -> Self {
+    fn div(self, other: Float) -> Self {
         Self::new(self.x / other, self.y / other, self.z / other)
     }
 }
 
 impl AbsDiffEq for Vector {
-    type Epsilon = f64;
+    type Epsilon = Float;
 
     fn default_epsilon() -> Self::Epsilon {
-        1e-4
+        EPSILON
     }
 
     fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
@@ -110,6 +157,7 @@ mod tests {
     use approx::assert_abs_diff_eq;
 
     use super::*;
+    use crate::utils::ROUNDING_EPSILON;
 
     #[test]
     fn sub_two_vectors() {
@@ -119,6 +167,16 @@ mod tests {
         assert_abs_diff_eq!(v1 - v2, Vector::new(-2.0, -4.0, -6.0));
     }
 
+    #[test]
+    fn add_assign_and_sub_assign_vectors() {
+        let mut v = Vector::new(3.0, 2.0, 1.0);
+        v += Vector::new(5.0, 6.0, 7.0);
+        assert_abs_diff_eq!(v, Vector::new(8.0, 8.0, 8.0));
+
+        v -= Vector::new(1.0, 2.0, 3.0);
+        assert_abs_diff_eq!(v, Vector::new(7.0, 6.0, 5.0));
+    }
+
     #[test]
     fn sub_vector_zero_vector() {
         let zero = Vector::new(0.0, 0.0, 0.0);
@@ -180,14 +238,14 @@ mod tests {
     fn magnitude_all() {
         let v = Vector::new(1.0, 2.0, 3.0);
 
-        assert_abs_diff_eq!(v.magnitude(), 14.0_f64.sqrt());
+        assert_abs_diff_eq!(v.magnitude(), Float::sqrt(14.0));
     }
 
     #[test]
     fn magnitude_negative() {
         let v = Vector::new(-1.0, -2.0, -3.0);
 
-        assert_abs_diff_eq!(v.magnitude(), 14.0_f64.sqrt());
+        assert_abs_diff_eq!(v.magnitude(), Float::sqrt(14.0));
     }
 
     #[test]
@@ -204,9 +262,9 @@ mod tests {
         assert_abs_diff_eq!(
             v.normalize(),
             Vector::new(
-                1.0 / 14.0_f64.sqrt(),
-                2.0 / 14.0_f64.sqrt(),
-                3.0 / 14.0_f64.sqrt()
+                1.0 / Float::sqrt(14.0),
+                2.0 / Float::sqrt(14.0),
+                3.0 / Float::sqrt(14.0)
             )
         );
     }
@@ -246,7 +304,73 @@ mod tests {
     #[test]
     fn reflecting_a_vector_off_a_slanted_surface() {
         let v = Vector::new(0.0, -1.0, 0.0);
-        let n = Vector::new(2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0, 0.0);
+        let n = Vector::new(Float::sqrt(2.0) / 2.0, Float::sqrt(2.0) / 2.0, 0.0);
         assert_abs_diff_eq!(v.reflect(n), Vector::new(1.0, 0.0, 0.0));
     }
+
+    #[test]
+    fn approx_eq_with_custom_epsilon() {
+        let a = Vector::new(1.0, 2.0, 3.0);
+        let b = Vector::new(1.001, 2.0, 2.999);
+
+        assert!(a.approx_eq(&b, 1e-2));
+        assert!(!a.approx_eq(&b, 1e-6));
+    }
+
+    #[test]
+    fn projection_and_rejection_reconstruct_the_vector() {
+        let v = Vector::new(3.0, -2.0, 5.0);
+        let onto = Vector::new(1.0, 1.0, 0.0);
+        let p = v.project_onto(onto);
+        let r = v.reject_from(onto);
+
+        assert_abs_diff_eq!(p, Vector::new(0.5, 0.5, 0.0));
+        assert_abs_diff_eq!(r.dot(onto), 0.0);
+        assert_abs_diff_eq!(p + r, v);
+    }
+
+    #[test]
+    fn projecting_onto_a_perpendicular_vector() {
+        let v = Vector::new(0.0, 4.0, 0.0);
+        let onto = Vector::new(2.0, 0.0, 0.0);
+
+        assert_abs_diff_eq!(v.project_onto(onto), Vector::new(0.0, 0.0, 0.0));
+        assert_abs_diff_eq!(v.reject_from(onto), v);
+    }
+
+    #[test]
+    fn lerp_between_vectors() {
+        let a = Vector::new(1.0, 0.0, 0.0);
+        let b = Vector::new(0.0, 2.0, 0.0);
+
+        assert_abs_diff_eq!(Vector::lerp(a, b, 0.0), a);
+        assert_abs_diff_eq!(Vector::lerp(a, b, 1.0), b);
+        assert_abs_diff_eq!(Vector::lerp(a, b, 0.5), Vector::new(0.5, 1.0, 0.0));
+    }
+
+    #[test]
+    fn slerp_halfway_between_perpendicular_vectors() {
+        let a = Vector::new(1.0, 0.0, 0.0);
+        let b = Vector::new(0.0, 0.0, 1.0);
+        let half = Vector::slerp(a, b, 0.5);
+
+        assert_abs_diff_eq!(half.magnitude(), 1.0, epsilon = ROUNDING_EPSILON);
+        assert_abs_diff_eq!(
+            half,
+            Vector::new(1.0, 0.0, 1.0).normalize(),
+            epsilon = ROUNDING_EPSILON
+        );
+        assert_abs_diff_eq!(
+            Vector::slerp(a, b, 1.0 / 3.0).dot(a),
+            (crate::utils::consts::PI / 6.0).cos(),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn slerp_of_parallel_vectors_falls_back_to_lerp() {
+        let a = Vector::new(0.0, 1.0, 0.0);
+
+        assert_abs_diff_eq!(Vector::slerp(a, a, 0.3), a);
+    }
 }
@@ -0,0 +1,50 @@
+use raytracer::utils::consts::PI;
+
+use approx::assert_abs_diff_eq;
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::matrix::Matrix;
+use raytracer::point::Point;
+use raytracer::test_fixtures::default_world;
+use raytracer::utils::EPSILON;
+use raytracer::vector::Vector;
+
+#[test]
+fn rendering_the_default_world() {
+    let world = default_world();
+    let mut camera = Camera::new(11, 11, PI / 2.0);
+    camera.transform = Matrix::view_transform(
+        Point::new(0.0, 0.0, -5.0),
+        Point::ORIGIN,
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    let image = camera.render(&world);
+
+    assert_abs_diff_eq!(
+        image.pixel_at(5, 5),
+        Color::new(0.38066, 0.47583, 0.2855),
+        epsilon = 1e-4
+    );
+}
+
+// run with and without `--features f32`; EPSILON loosens to match the precision
+#[test]
+fn default_world_renders_at_either_precision() {
+    let world = default_world();
+    let mut camera = Camera::new(21, 21, PI / 2.0);
+    camera.transform = Matrix::view_transform(
+        Point::new(0.0, 0.0, -5.0),
+        Point::ORIGIN,
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    let image = camera.render(&world);
+
+    for c in &image.pixels {
+        assert!(c.red.is_finite() && c.green.is_finite() && c.blue.is_finite());
+    }
+    assert_abs_diff_eq!(
+        image.pixel_at(10, 10),
+        Color::new(0.38066, 0.47583, 0.2855),
+        epsilon = EPSILON
+    );
+}
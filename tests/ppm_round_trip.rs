@@ -0,0 +1,64 @@
+use std::fs;
+
+use raytracer::canvas::Canvas;
+use raytracer::color::Color;
+use raytracer::utils::Float;
+
+// every value is a multiple of 1/255 (or clamps to one), so it survives 8 bits
+fn known_canvas() -> Canvas {
+    let level = |v: u8| Float::from(v) / 255.0;
+    Canvas::from_pixels(
+        3,
+        2,
+        vec![
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(level(51), level(102), level(153)),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.5, -0.5, level(7)), // out of range, clamped on write
+            Color::WHITE,
+            Color::BLACK,
+        ],
+    )
+}
+
+fn assert_well_formed(ppm: &str, max_len: usize) {
+    assert!(ppm.ends_with('\n'));
+    for line in ppm.lines().skip(3) {
+        assert!(line.len() <= max_len, "{} chars: {line:?}", line.len());
+    }
+}
+
+#[test]
+fn ppm_file_round_trips_pixel_for_pixel() {
+    let dir = std::env::temp_dir().join(format!("ppm_round_trip_{}", std::process::id()));
+    let path = dir.join("known.ppm");
+    let canvas = known_canvas();
+
+    canvas.write_ppm(path.to_str().unwrap());
+    let ppm = fs::read_to_string(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_well_formed(&ppm, 70);
+    assert!(ppm.starts_with("P3\n3 2\n255\n"));
+
+    let read = Canvas::from_ppm(&ppm).unwrap();
+    assert_eq!((read.width, read.height), (3, 2));
+
+    let mut expected = canvas.pixels.clone();
+    expected[3] = Color::new(1.0, 0.0, 7.0 / 255.0);
+    assert_eq!(read.pixels, expected);
+}
+
+#[test]
+fn wrapped_ppm_data_round_trips_and_respects_the_line_length() {
+    let canvas = known_canvas();
+
+    for max_len in [11, 20, 35] {
+        let ppm = canvas.to_ppm_with_line_len(max_len);
+        assert_well_formed(&ppm, max_len);
+        assert_eq!(
+            Canvas::from_ppm(&ppm).unwrap(),
+            Canvas::from_ppm(&canvas.to_ppm()).unwrap()
+        );
+    }
+}